@@ -6,16 +6,10 @@ use crate::{
     },
 };
 use gpui::{App, Entity};
-use std::{
-    sync::Arc,
-    time::{Duration, Instant},
-};
+use std::{sync::Arc, time::Duration};
 
 pub fn spawn_event_loop(cx: &mut App, controller: Controller, arc_res: Arc<Entity<ResHandler>>) {
     cx.spawn(async move |cx| {
-        let mut last_pos_request = Instant::now();
-        let mut last_track_ended_request = Instant::now();
-
         loop {
             while let Ok(e) = controller.audio_rx.try_recv() {
                 arc_res
@@ -65,20 +59,41 @@ pub fn spawn_event_loop(cx: &mut App, controller: Controller, arc_res: Arc<Entit
                     .ok();
             }
 
-            if last_pos_request.elapsed() >= Duration::from_millis(16) {
-                controller.get_pos();
-
-                last_pos_request = Instant::now();
+            while let Ok(e) = controller.supervisor_rx.try_recv() {
+                arc_res
+                    .update(cx, |res_handler, cx| {
+                        res_handler.handle(cx, Event::Supervisor(e));
+                    })
+                    .ok();
             }
 
-            if last_track_ended_request.elapsed() >= Duration::from_millis(512) {
-                controller.check_track_ended();
+            cx.background_executor()
+                .timer(Duration::from_millis(16))
+                .await;
+        }
+    })
+    .detach();
+}
 
-                last_track_ended_request = Instant::now();
-            }
+// Watches for a due alarm, and keeps the volume slider up with its ramp once it goes off.
+pub fn spawn_alarm_clock(cx: &mut App, controller: Controller, view: Entity<Wiremann>) {
+    cx.spawn(async move |cx| {
+        loop {
+            view.update(cx, |this, cx| {
+                if let Some(volume) = controller.tick_alarm(cx) {
+                    this.player_page.update(cx, |this, cx| {
+                        this.controlbar.update(cx, |this, cx| {
+                            this.vol_slider_state.update(cx, |this, cx| {
+                                this.set_value(volume * 100.0, cx);
+                            });
+                        });
+                    });
+                }
+            })
+            .ok();
 
             cx.background_executor()
-                .timer(Duration::from_millis(16))
+                .timer(Duration::from_millis(250))
                 .await;
         }
     })
@@ -103,6 +118,7 @@ pub fn subscribe_controller_events(
                 controller.handle_system_integration_event(cx, event, &view)
             }
             Event::LyricsEvent(event) => controller.handle_lyrics_event(cx, event, &view),
+            Event::Supervisor(event) => controller.handle_supervisor_event(cx, event, &view),
         } {
             tracing::error!(error = ?e, "Controller error occured");
         }
@@ -9,21 +9,25 @@ use crate::lyrics_manager::LyricsManager;
 use crate::system_integration::SystemIntegration;
 use crate::{
     audio::Audio,
-    controller::{Controller, state::AppState},
+    controller::{
+        Controller,
+        state::{AppState, Engine},
+    },
     errors::AppError,
     scanner::Scanner,
     ui::{assets::Assets, res_handler::ResHandler, wiremann::Wiremann},
 };
 pub use paths::*;
+pub use workers::spawn_pool_worker;
 
 use gpui::{AppContext, Application, Result};
 use raw_window_handle::HasWindowHandle;
 use std::sync::Arc;
 use tracing::info;
 
-use events::{spawn_event_loop, subscribe_controller_events};
+use events::{spawn_alarm_clock, spawn_event_loop, subscribe_controller_events};
 use window::build_window_options;
-use workers::{WorkerConfig, calculate_worker_config, spawn_worker};
+use workers::{EngineRun, WorkerConfig, calculate_worker_config, spawn_worker, supervise};
 
 static ICON_PNG: &[u8] = include_bytes!("../../assets/logos/logo.png");
 
@@ -49,23 +53,77 @@ pub fn run(app_paths: AppPaths) -> Result<(), AppError> {
             cx.open_window(window_options, |window, cx| {
                 info!("Initializing engines...");
 
-                let (mut audio, audio_tx, audio_rx) = Audio::new();
+                let (supervisor_tx, supervisor_rx) = crossbeam_channel::unbounded();
 
-                let (mut scanner, scanner_tx, scanner_rx) = Scanner::new(app_paths.clone());
+                let (audio_tx, audio_rx) = supervise(
+                    Engine::Audio,
+                    || {
+                        let (mut audio, tx, rx) = Audio::new();
+                        (Box::new(move || audio.run()) as EngineRun<_>, tx, rx)
+                    },
+                    supervisor_tx.clone(),
+                );
+
+                let (scanner_tx, scanner_rx) = supervise(
+                    Engine::Scanner,
+                    {
+                        let app_paths = app_paths.clone();
+                        move || {
+                            let (mut scanner, tx, rx) = Scanner::new(app_paths.clone());
+                            let run = move || scanner.run(metadata_workers);
+                            (Box::new(run) as EngineRun<_>, tx, rx)
+                        }
+                    },
+                    supervisor_tx.clone(),
+                );
 
-                let (cacher, cacher_tx, cacher_rx) = Cacher::new(app_paths.clone());
+                let (cacher_tx, cacher_rx) = supervise(
+                    Engine::Cacher,
+                    {
+                        let app_paths = app_paths.clone();
+                        move || {
+                            let (cacher, tx, rx) = Cacher::new(app_paths.clone());
+                            let run = move || cacher.run(cacher_workers);
+                            (Box::new(run) as EngineRun<_>, tx, rx)
+                        }
+                    },
+                    supervisor_tx.clone(),
+                );
 
-                let (mut image_processor, image_processor_tx, image_processor_rx) =
-                    ImageProcessor::new(app_paths.clone());
+                let (image_processor_tx, image_processor_rx) = supervise(
+                    Engine::ImageProcessor,
+                    {
+                        let app_paths = app_paths.clone();
+                        move || {
+                            let (mut image_processor, tx, rx) =
+                                ImageProcessor::new(app_paths.clone());
+                            let run = move || image_processor.run(thumbnail_workers);
+                            (Box::new(run) as EngineRun<_>, tx, rx)
+                        }
+                    },
+                    supervisor_tx.clone(),
+                );
+
+                let (lyrics_manager_tx, lyrics_manager_rx) = supervise(
+                    Engine::LyricsManager,
+                    || {
+                        let (mut lyrics_manager, tx, rx) = LyricsManager::new();
+                        (
+                            Box::new(move || lyrics_manager.run()) as EngineRun<_>,
+                            tx,
+                            rx,
+                        )
+                    },
+                    supervisor_tx,
+                );
 
+                // The media controls are bound to the window handle, which can't be handed
+                // to another thread to rebuild them, so this one isn't supervised.
                 let raw_window_handle = window.window_handle().ok().map(|this| this.as_raw());
 
                 let (mut system_integration, system_integration_tx, system_integration_rx) =
                     SystemIntegration::new(raw_window_handle, app_paths);
 
-                let (mut lyrics_manager, lyrics_manager_tx, lyrics_manager_rx) =
-                    LyricsManager::new();
-
                 let controller = Controller::new(
                     cx.new(|_| AppState::default()),
                     audio_tx,
@@ -80,22 +138,11 @@ pub fn run(app_paths: AppPaths) -> Result<(), AppError> {
                     system_integration_rx,
                     lyrics_manager_tx,
                     lyrics_manager_rx,
+                    supervisor_rx,
                 );
 
-                spawn_worker("audio", move || audio.run());
-
-                spawn_worker("scanner", move || scanner.run(metadata_workers));
-
-                spawn_worker("cacher", move || cacher.run(cacher_workers));
-
-                spawn_worker("image processor", move || {
-                    image_processor.run(thumbnail_workers)
-                });
-
                 spawn_worker("system integration", move || system_integration.run());
 
-                spawn_worker("lyrics manager", move || lyrics_manager.run());
-
                 cx.set_global(controller.clone());
 
                 let view = cx.new(Wiremann::new);
@@ -108,6 +155,8 @@ pub fn run(app_paths: AppPaths) -> Result<(), AppError> {
 
                 subscribe_controller_events(cx, &res_handler, controller.clone(), view.clone());
 
+                spawn_alarm_clock(cx, controller.clone(), view.clone());
+
                 view
             })
             .expect("Application panicked.");
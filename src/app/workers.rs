@@ -1,9 +1,23 @@
 use std::{
+    any::Any,
     panic::{AssertUnwindSafe, catch_unwind},
     thread,
+    time::{Duration, Instant},
 };
 
-use tracing::{error, info};
+use crate::controller::{
+    events::SupervisorEvent,
+    state::{Engine, EngineHealth},
+};
+use crossbeam_channel::{Receiver, Sender, never, select};
+use tracing::{error, info, warn};
+
+const MAX_RESTARTS: u32 = 5;
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// An engine that stayed up this long is considered healthy again, and its restart budget
+// starts over.
+const STABLE_AFTER: Duration = Duration::from_mins(1);
 
 pub struct WorkerConfig {
     pub metadata: usize,
@@ -11,6 +25,13 @@ pub struct WorkerConfig {
     pub cacher: usize,
 }
 
+pub type EngineRun<E> = Box<dyn FnOnce() -> Result<(), E> + Send>;
+
+enum Exit {
+    Clean,
+    Crashed(String),
+}
+
 pub fn spawn_worker<F, E>(name: &'static str, f: F)
 where
     F: FnOnce() -> Result<(), E> + Send + 'static,
@@ -21,23 +42,205 @@ where
         .spawn(move || {
             info!("Spawning worker thread for [{name}] engine...");
 
-            let result = catch_unwind(AssertUnwindSafe(f));
+            run_guarded(f);
+        })
+        .unwrap_or_else(|e| {
+            panic!("failed to spawn worker thread '{name}': {e}");
+        });
+}
+
+/// Spawns one of an engine's own worker threads. If it panics, the message goes out on
+/// `crashed` so the engine can fail and be restarted by its supervisor, rather than
+/// carry on with the worker missing.
+pub fn spawn_pool_worker<F>(name: &'static str, crashed: &Sender<String>, f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let crashed = crashed.clone();
+
+    thread::Builder::new()
+        .name(name.into())
+        .spawn(move || {
+            if let Err(e) = catch_unwind(AssertUnwindSafe(f)) {
+                let message = panic_message(e.as_ref());
+                error!(panic = %message, "[{name}] worker panicked");
+
+                crashed.send(message).ok();
+            }
+        })
+        .unwrap_or_else(|e| {
+            panic!("failed to spawn worker thread '{name}': {e}");
+        });
+}
+
+/// Runs an engine under a supervisor that rebuilds it with `build` whenever it crashes.
+///
+/// The returned channels stay valid across restarts: the supervisor forwards them to
+/// whichever instance is currently running. Commands that were queued inside a crashed
+/// instance are lost, so the controller re-requests its state on `Restarted`.
+pub fn supervise<C, V, E, F>(
+    engine: Engine,
+    mut build: F,
+    health_tx: Sender<SupervisorEvent>,
+) -> (Sender<C>, Receiver<V>)
+where
+    C: Send + 'static,
+    V: Send + 'static,
+    E: core::fmt::Debug + 'static,
+    F: FnMut() -> (EngineRun<E>, Sender<C>, Receiver<V>) + Send + 'static,
+{
+    let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
+    let (event_tx, event_rx) = crossbeam_channel::unbounded();
+
+    let name = engine.name();
+
+    thread::Builder::new()
+        .name(format!("{name} supervisor"))
+        .spawn(move || {
+            let mut failures = 0;
+
+            loop {
+                let (run, engine_tx, engine_rx) = build();
+                let (exit_tx, exit_rx) = crossbeam_channel::bounded(1);
 
-            match result {
-                Ok(Ok(())) => {
-                    info!("worker exited cleanly");
+                thread::Builder::new()
+                    .name(name.into())
+                    .spawn(move || {
+                        info!("Spawning worker thread for [{name}] engine...");
+
+                        exit_tx.send(run_guarded(run)).ok();
+                    })
+                    .unwrap_or_else(|e| {
+                        panic!("failed to spawn worker thread '{name}': {e}");
+                    });
+
+                let started = Instant::now();
+
+                health_tx
+                    .send(SupervisorEvent::Health(engine, EngineHealth::Running))
+                    .ok();
+                if failures > 0 {
+                    health_tx.send(SupervisorEvent::Restarted(engine)).ok();
                 }
-                Ok(Err(e)) => {
-                    error!(error = ?e, "worker crashed");
+
+                // `None` means the controller hung up, i.e. the app is shutting down.
+                let Some(exit) = forward(&cmd_rx, &event_tx, &engine_tx, engine_rx, &exit_rx)
+                else {
+                    return;
+                };
+
+                let error = match exit {
+                    Exit::Clean => {
+                        health_tx
+                            .send(SupervisorEvent::Health(engine, EngineHealth::Stopped))
+                            .ok();
+                        return;
+                    }
+                    Exit::Crashed(error) => error,
+                };
+
+                if started.elapsed() >= STABLE_AFTER {
+                    failures = 0;
                 }
-                Err(e) => {
-                    error!(panic = ?e, "worker panicked");
+                failures += 1;
+
+                if failures > MAX_RESTARTS {
+                    error!("[{name}] engine keeps crashing, giving up");
+
+                    health_tx
+                        .send(SupervisorEvent::Health(engine, EngineHealth::Failed(error)))
+                        .ok();
+                    return;
                 }
+
+                let delay = backoff(failures);
+                warn!(attempt = failures, ?delay, "Restarting [{name}] engine");
+
+                health_tx
+                    .send(SupervisorEvent::Health(
+                        engine,
+                        EngineHealth::Restarting {
+                            attempt: failures,
+                            error,
+                        },
+                    ))
+                    .ok();
+
+                thread::sleep(delay);
             }
         })
         .unwrap_or_else(|e| {
-            panic!("failed to spawn worker thread '{name}': {e}");
+            panic!("failed to spawn supervisor thread for '{name}': {e}");
         });
+
+    (cmd_tx, event_rx)
+}
+
+fn forward<C, V>(
+    commands: &Receiver<C>,
+    events: &Sender<V>,
+    engine_tx: &Sender<C>,
+    mut engine_rx: Receiver<V>,
+    exit_rx: &Receiver<Exit>,
+) -> Option<Exit> {
+    loop {
+        select! {
+            recv(commands) -> cmd => match cmd {
+                Ok(cmd) => {
+                    engine_tx.send(cmd).ok();
+                }
+                Err(_) => return None,
+            },
+            recv(engine_rx) -> event => match event {
+                Ok(event) => {
+                    events.send(event).ok();
+                }
+                Err(_) => engine_rx = never(),
+            },
+            recv(exit_rx) -> exit => {
+                for event in engine_rx.try_iter() {
+                    events.send(event).ok();
+                }
+
+                return Some(exit.unwrap_or_else(|_| Exit::Crashed("worker vanished".into())));
+            }
+        }
+    }
+}
+
+fn backoff(attempt: u32) -> Duration {
+    BASE_BACKOFF
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(MAX_BACKOFF)
+}
+
+fn run_guarded<F, E>(f: F) -> Exit
+where
+    F: FnOnce() -> Result<(), E>,
+    E: core::fmt::Debug,
+{
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => {
+            info!("worker exited cleanly");
+            Exit::Clean
+        }
+        Ok(Err(e)) => {
+            error!(error = ?e, "worker crashed");
+            Exit::Crashed(format!("{e:?}"))
+        }
+        Err(e) => {
+            error!(panic = ?e, "worker panicked");
+            Exit::Crashed(panic_message(e.as_ref()))
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic".to_string())
 }
 
 pub fn calculate_worker_config() -> WorkerConfig {
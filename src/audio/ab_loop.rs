@@ -0,0 +1,125 @@
+use super::stretch::Position;
+use crossbeam_channel::Sender;
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// The A-B loop of one track, in nanoseconds. An end of zero means there's no loop.
+pub struct LoopControl {
+    start: AtomicU64,
+    end: AtomicU64,
+}
+
+impl LoopControl {
+    #[must_use]
+    pub fn new() -> Self {
+        LoopControl {
+            start: AtomicU64::new(0),
+            end: AtomicU64::new(0),
+        }
+    }
+
+    pub fn set(&self, start: Duration, end: Duration) {
+        // Off while the points change, so the output thread never sees half of each.
+        self.end.store(0, Ordering::Release);
+        self.start.store(nanos(start), Ordering::Release);
+        self.end.store(nanos(end), Ordering::Release);
+    }
+
+    pub fn clear(&self) {
+        self.end.store(0, Ordering::Release);
+    }
+
+    fn get(&self) -> Option<(Duration, Duration)> {
+        let end = self.end.load(Ordering::Acquire);
+
+        (end > 0).then(|| {
+            (
+                Duration::from_nanos(self.start.load(Ordering::Acquire)),
+                Duration::from_nanos(end),
+            )
+        })
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+// Seeks back to the loop start as soon as playback reaches the end, on the first frame
+// boundary past it. Sources that can't seek drop the loop and send the error on `failed`.
+pub struct AbLoop<S> {
+    input: S,
+    control: Arc<LoopControl>,
+    position: Arc<Position>,
+    failed: Sender<SeekError>,
+
+    channels: usize,
+    sample_in_frame: usize,
+}
+
+impl<S: Source> AbLoop<S> {
+    pub fn new(
+        input: S,
+        control: Arc<LoopControl>,
+        position: Arc<Position>,
+        failed: Sender<SeekError>,
+    ) -> Self {
+        let channels = usize::from(input.channels().get());
+
+        AbLoop {
+            input,
+            control,
+            position,
+            failed,
+            channels,
+            sample_in_frame: 0,
+        }
+    }
+}
+
+impl<S: Source> Iterator for AbLoop<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if self.sample_in_frame == 0
+            && let Some((start, end)) = self.control.get()
+            && self.position.get() >= end
+            && let Err(e) = self.input.try_seek(start)
+        {
+            self.control.clear();
+            self.failed.send(e).ok();
+        }
+
+        let sample = self.input.next()?;
+        self.sample_in_frame = (self.sample_in_frame + 1) % self.channels;
+
+        Some(sample)
+    }
+}
+
+impl<S: Source> Source for AbLoop<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.sample_in_frame = 0;
+
+        Ok(())
+    }
+}
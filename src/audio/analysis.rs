@@ -0,0 +1,249 @@
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::time::Duration;
+
+const TAP_SIZE: usize = 4096;
+const FFT_SIZE: usize = 2048;
+const BANDS: usize = 48;
+const WAVEFORM_POINTS: usize = 128;
+const MIN_FREQUENCY: f32 = 40.0;
+const MAX_FREQUENCY: f32 = 16_000.0;
+const FLOOR_DB: f32 = -72.0;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Analysis {
+    pub spectrum: Vec<f32>,
+    pub waveform: Vec<f32>,
+    pub rms: f32,
+    pub peak: f32,
+}
+
+// Ring of mono samples written from the playback thread with plain atomics, so the tap
+// never blocks or allocates. Readers may see a partially overwritten window, which is
+// harmless for display purposes.
+pub struct TapBuffer {
+    samples: Box<[AtomicU32]>,
+    written: AtomicUsize,
+    rate: AtomicU32,
+}
+
+impl TapBuffer {
+    #[must_use]
+    pub fn new() -> Self {
+        TapBuffer {
+            samples: (0..TAP_SIZE).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            rate: AtomicU32::new(44_100),
+        }
+    }
+
+    fn push(&self, sample: f32) {
+        let index = self.written.fetch_add(1, Ordering::Relaxed) & (TAP_SIZE - 1);
+        self.samples[index].store(sample.to_bits(), Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, out: &mut [f32]) {
+        let end = self.written.load(Ordering::Relaxed);
+        let start = end.wrapping_sub(out.len());
+
+        for (i, sample) in out.iter_mut().enumerate() {
+            let index = start.wrapping_add(i) & (TAP_SIZE - 1);
+            *sample = f32::from_bits(self.samples[index].load(Ordering::Relaxed));
+        }
+    }
+}
+
+pub struct Tap<S> {
+    input: S,
+    buffer: Arc<TapBuffer>,
+    channels: u16,
+    sum: f32,
+    index: u16,
+}
+
+impl<S: Source> Tap<S> {
+    pub fn new(input: S, buffer: Arc<TapBuffer>) -> Self {
+        buffer
+            .rate
+            .store(input.sample_rate().get(), Ordering::Relaxed);
+
+        Tap {
+            channels: input.channels().get(),
+            input,
+            buffer,
+            sum: 0.0,
+            index: 0,
+        }
+    }
+}
+
+impl<S: Source> Iterator for Tap<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let sample = self.input.next()?;
+
+        self.sum += sample;
+        self.index += 1;
+
+        if self.index >= self.channels {
+            self.buffer.push(self.sum / f32::from(self.channels));
+            self.sum = 0.0;
+            self.index = 0;
+        }
+
+        Some(sample)
+    }
+}
+
+impl<S: Source> Source for Tap<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+pub struct Analyzer {
+    samples: Vec<f32>,
+    window: Vec<f32>,
+    window_sum: f32,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl Analyzer {
+    #[must_use]
+    pub fn new() -> Self {
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| (PI * i as f32 / FFT_SIZE as f32).sin().powi(2))
+            .collect();
+
+        Analyzer {
+            samples: vec![0.0; FFT_SIZE],
+            window_sum: window.iter().sum(),
+            window,
+            re: vec![0.0; FFT_SIZE],
+            im: vec![0.0; FFT_SIZE],
+        }
+    }
+
+    pub fn analyze(&mut self, buffer: &TapBuffer) -> Analysis {
+        buffer.snapshot(&mut self.samples);
+
+        let rate = buffer.rate.load(Ordering::Relaxed).max(1) as f32;
+
+        let peak = self
+            .samples
+            .iter()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let rms = (self.samples.iter().map(|s| s * s).sum::<f32>() / FFT_SIZE as f32).sqrt();
+
+        let waveform = self.samples[FFT_SIZE - WAVEFORM_POINTS * 4..]
+            .iter()
+            .step_by(4)
+            .copied()
+            .collect();
+
+        for ((re, sample), window) in self.re.iter_mut().zip(&self.samples).zip(&self.window) {
+            *re = sample * window;
+        }
+        self.im.fill(0.0);
+
+        fft(&mut self.re, &mut self.im);
+
+        let bin =
+            |frequency: f32| ((frequency * FFT_SIZE as f32 / rate) as usize).min(FFT_SIZE / 2);
+        let ratio = MAX_FREQUENCY / MIN_FREQUENCY;
+
+        let spectrum = (0..BANDS)
+            .map(|band| {
+                let low = MIN_FREQUENCY * ratio.powf(band as f32 / BANDS as f32);
+                let high = MIN_FREQUENCY * ratio.powf((band + 1) as f32 / BANDS as f32);
+
+                let start = bin(low);
+                let end = bin(high).max(start + 1).min(FFT_SIZE / 2);
+
+                let magnitude = (start..end)
+                    .map(|k| (self.re[k] * self.re[k] + self.im[k] * self.im[k]).sqrt())
+                    .fold(0.0f32, f32::max)
+                    * 2.0
+                    / self.window_sum;
+
+                let db = 20.0 * (magnitude + 1e-9).log10();
+
+                ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+            })
+            .collect();
+
+        Analysis {
+            spectrum,
+            waveform,
+            rms,
+            peak,
+        }
+    }
+}
+
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let (w_im, w_re) = (-2.0 * PI / len as f32).sin_cos();
+        let half = len / 2;
+
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f32, 0.0f32);
+
+            for k in 0..half {
+                let (a, b) = (start + k, start + k + half);
+
+                let t_re = re[b] * cur_re - im[b] * cur_im;
+                let t_im = re[b] * cur_im + im[b] * cur_re;
+
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+
+        len <<= 1;
+    }
+}
@@ -0,0 +1,216 @@
+use crate::controller::state::{EqBand, EqFilter};
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub struct EqControl {
+    enabled: AtomicBool,
+    version: AtomicU64,
+    bands: Mutex<Vec<EqBand>>,
+}
+
+impl EqControl {
+    #[must_use]
+    pub fn new() -> Self {
+        EqControl {
+            enabled: AtomicBool::new(false),
+            version: AtomicU64::new(0),
+            bands: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn set_bands(&self, bands: Vec<EqBand>) {
+        if let Ok(mut current) = self.bands.lock()
+            && *current != bands
+        {
+            *current = bands;
+            self.version.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    // `None` while `set_bands` is writing, so the output thread never waits on the engine.
+    fn try_bands(&self) -> Option<Vec<EqBand>> {
+        self.bands.try_lock().ok().map(|bands| bands.clone())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Coefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Coefficients {
+    fn new(band: &EqBand, rate: f32) -> Option<Self> {
+        if band.gain.abs() < f32::EPSILON || band.frequency >= rate * 0.45 {
+            return None;
+        }
+
+        let a = 10f32.powf(band.gain / 40.0);
+        let w0 = 2.0 * PI * band.frequency / rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * band.q.max(0.1));
+        let sqrt_a = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match band.filter {
+            EqFilter::Peaking => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            EqFilter::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a),
+                (a + 1.0) + (a - 1.0) * cos + sqrt_a,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - sqrt_a,
+            ),
+            EqFilter::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a),
+                (a + 1.0) - (a - 1.0) * cos + sqrt_a,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - sqrt_a,
+            ),
+        };
+
+        Some(Coefficients {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        })
+    }
+}
+
+pub struct Equalizer<S> {
+    input: S,
+    control: Arc<EqControl>,
+
+    version: Option<u64>,
+    filters: Vec<Coefficients>,
+    // One `[z1, z2]` pair per filter, per channel.
+    state: Vec<[f32; 2]>,
+    channel: usize,
+}
+
+impl<S: Source> Equalizer<S> {
+    pub fn new(input: S, control: Arc<EqControl>) -> Self {
+        Equalizer {
+            input,
+            control,
+            version: None,
+            filters: Vec::new(),
+            state: Vec::new(),
+            channel: 0,
+        }
+    }
+
+    // Keeps the current filters if the bands are being written, and tries again on the
+    // next sample.
+    fn rebuild(&mut self, version: u64) {
+        let Some(bands) = self.control.try_bands() else {
+            return;
+        };
+
+        let rate = self.input.sample_rate().get() as f32;
+        let channels = usize::from(self.input.channels().get());
+
+        self.filters = bands
+            .iter()
+            .filter_map(|band| Coefficients::new(band, rate))
+            .collect();
+        self.state = vec![[0.0; 2]; self.filters.len() * channels];
+        self.version = Some(version);
+    }
+}
+
+impl<S: Source> Iterator for Equalizer<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        let sample = self.input.next()?;
+
+        let channels = usize::from(self.input.channels().get());
+        let channel = self.channel % channels;
+        self.channel = (channel + 1) % channels;
+
+        if !self.control.enabled() {
+            return Some(sample);
+        }
+
+        let version = self.control.version();
+        if self.version != Some(version) || self.state.len() != self.filters.len() * channels {
+            self.rebuild(version);
+        }
+
+        // Still waiting to rebuild for a new channel count.
+        if self.state.len() != self.filters.len() * channels {
+            return Some(sample);
+        }
+
+        let offset = channel * self.filters.len();
+        let mut x = sample;
+
+        for (filter, z) in self.filters.iter().zip(self.state[offset..].iter_mut()) {
+            let y = filter.b0 * x + z[0];
+            z[0] = filter.b1 * x - filter.a1 * y + z[1];
+            z[1] = filter.b2 * x - filter.a2 * y;
+            x = y;
+        }
+
+        Some(x)
+    }
+}
+
+impl<S: Source> Source for Equalizer<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+
+        self.channel = 0;
+        for z in &mut self.state {
+            *z = [0.0; 2];
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::controller::state::FadeCurve;
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::f32::consts::FRAC_PI_2;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+pub struct Fader<S> {
+    input: S,
+    curve: FadeCurve,
+
+    fade_len: u64,
+    fade_in_remaining: u64,
+    fade_out_elapsed: Option<u64>,
+
+    elapsed: u64,
+    end_at: Option<u64>,
+
+    fade_out_now: Arc<AtomicBool>,
+    on_fade_out: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl<S: Source> Fader<S> {
+    pub fn new(
+        input: S,
+        length: Duration,
+        curve: FadeCurve,
+        fade_in: bool,
+        fade_out_now: Arc<AtomicBool>,
+        on_fade_out: Box<dyn FnOnce() + Send>,
+    ) -> Self {
+        let samples_per_sec =
+            f64::from(input.sample_rate().get()) * f64::from(input.channels().get());
+        let to_samples = |d: Duration| (d.as_secs_f64() * samples_per_sec) as u64;
+
+        let fade_len = to_samples(length).max(1);
+        let end_at = input.total_duration().map(to_samples);
+
+        Fader {
+            input,
+            curve,
+            fade_len,
+            fade_in_remaining: if fade_in { fade_len } else { 0 },
+            fade_out_elapsed: None,
+            elapsed: 0,
+            end_at,
+            fade_out_now,
+            on_fade_out: Some(on_fade_out),
+        }
+    }
+
+    fn notify(&mut self) {
+        if let Some(callback) = self.on_fade_out.take() {
+            callback();
+        }
+    }
+
+    fn gain(&self, progress: u64) -> f32 {
+        let t = (progress as f32 / self.fade_len as f32).clamp(0.0, 1.0);
+
+        match self.curve {
+            FadeCurve::Linear => t,
+            FadeCurve::EqualPower => (t * FRAC_PI_2).sin(),
+        }
+    }
+}
+
+impl<S: Source> Iterator for Fader<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if self.fade_out_elapsed.is_none() {
+            let reached_end = self
+                .end_at
+                .is_some_and(|end| self.elapsed + self.fade_len >= end);
+
+            if reached_end || self.fade_out_now.load(Ordering::Relaxed) {
+                self.fade_out_elapsed = Some(0);
+                self.notify();
+            }
+        }
+
+        let Some(sample) = self.input.next() else {
+            self.notify();
+            return None;
+        };
+
+        self.elapsed += 1;
+
+        let mut gain = 1.0;
+
+        if self.fade_in_remaining > 0 {
+            gain *= self.gain(self.fade_len - self.fade_in_remaining);
+            self.fade_in_remaining -= 1;
+        }
+
+        if let Some(done) = self.fade_out_elapsed {
+            if done >= self.fade_len {
+                return None;
+            }
+
+            gain *= self.gain(self.fade_len - done);
+            self.fade_out_elapsed = Some(done + 1);
+        }
+
+        Some(sample * gain)
+    }
+}
+
+impl<S: Source> Source for Fader<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+
+        if self.fade_out_elapsed.is_none() {
+            let samples_per_sec =
+                f64::from(self.sample_rate().get()) * f64::from(self.channels().get());
+            self.elapsed = (pos.as_secs_f64() * samples_per_sec) as u64;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+use rodio::decoder::DecoderBuilder;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const SNIFF_LEN: usize = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Format {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    pub mime: &'static str,
+    // Whether the decoders compiled into rodio can play it. Formats without a decoder are
+    // still listed so that sniffing can tell them apart from a mislabelled playable file.
+    pub decodable: bool,
+}
+
+pub const MP3: Format = Format {
+    name: "MP3",
+    extensions: &["mp3", "mp2", "mp1"],
+    mime: "audio/mpeg",
+    decodable: true,
+};
+
+pub const AAC: Format = Format {
+    name: "AAC",
+    extensions: &["aac", "adts"],
+    mime: "audio/aac",
+    decodable: true,
+};
+
+pub const MP4: Format = Format {
+    name: "MPEG-4 Audio",
+    extensions: &["m4a", "m4b", "mp4", "alac"],
+    mime: "audio/mp4",
+    decodable: true,
+};
+
+pub const FLAC: Format = Format {
+    name: "FLAC",
+    extensions: &["flac"],
+    mime: "audio/flac",
+    decodable: true,
+};
+
+pub const VORBIS: Format = Format {
+    name: "Ogg Vorbis",
+    extensions: &["ogg", "oga"],
+    mime: "audio/ogg",
+    decodable: true,
+};
+
+pub const OPUS: Format = Format {
+    name: "Opus",
+    extensions: &["opus"],
+    mime: "audio/opus",
+    decodable: false,
+};
+
+pub const WAV: Format = Format {
+    name: "WAV",
+    extensions: &["wav", "wave"],
+    mime: "audio/wav",
+    decodable: true,
+};
+
+pub const AIFF: Format = Format {
+    name: "AIFF",
+    extensions: &["aiff", "aif", "aifc"],
+    mime: "audio/aiff",
+    decodable: true,
+};
+
+pub const CAF: Format = Format {
+    name: "Core Audio",
+    extensions: &["caf"],
+    mime: "audio/x-caf",
+    decodable: true,
+};
+
+pub const MATROSKA: Format = Format {
+    name: "Matroska Audio",
+    extensions: &["mka"],
+    mime: "audio/x-matroska",
+    decodable: true,
+};
+
+pub const WAVPACK: Format = Format {
+    name: "WavPack",
+    extensions: &["wv"],
+    mime: "audio/x-wavpack",
+    decodable: false,
+};
+
+pub const APE: Format = Format {
+    name: "Monkey's Audio",
+    extensions: &["ape"],
+    mime: "audio/x-ape",
+    decodable: false,
+};
+
+pub const FORMATS: &[&Format] = &[
+    &MP3, &AAC, &MP4, &FLAC, &VORBIS, &OPUS, &WAV, &AIFF, &CAF, &MATROSKA, &WAVPACK, &APE,
+];
+
+#[must_use]
+pub fn from_extension(path: &Path) -> Option<&'static Format> {
+    let ext = path
+        .extension()
+        .and_then(OsStr::to_str)?
+        .to_ascii_lowercase();
+
+    FORMATS
+        .iter()
+        .copied()
+        .find(|format| format.extensions.contains(&ext.as_str()))
+}
+
+/// Maps a `Content-Type` to a format, for network streams that have no extension to go by.
+#[must_use]
+pub fn from_mime(mime: &str) -> Option<&'static Format> {
+    let mime = mime.split(';').next()?.trim().to_ascii_lowercase();
+
+    match mime.as_str() {
+        "audio/mp3" | "audio/mpeg3" => Some(&MP3),
+        "audio/aacp" | "audio/x-aac" => Some(&AAC),
+        "application/ogg" | "audio/vorbis" => Some(&VORBIS),
+        "audio/x-flac" => Some(&FLAC),
+        _ => FORMATS.iter().copied().find(|format| format.mime == mime),
+    }
+}
+
+/// Extensions of every format we can play, for file pickers.
+#[must_use]
+pub fn extensions() -> Vec<&'static str> {
+    FORMATS
+        .iter()
+        .filter(|format| format.decodable)
+        .flat_map(|format| format.extensions.iter().copied())
+        .collect()
+}
+
+/// Files worth opening to sniff: anything with a known audio extension. Sniffing only
+/// catches files labelled as the wrong format, so the rest of a folder is left unopened.
+#[must_use]
+pub fn is_candidate(path: &Path) -> bool {
+    from_extension(path).is_some()
+}
+
+/// Detects the format from the file contents, falling back to the extension when the
+/// header is inconclusive (raw MPEG streams don't always start on a frame).
+#[must_use]
+pub fn detect(path: &Path) -> Option<&'static Format> {
+    match sniff_file(path) {
+        Ok(Some(format)) => Some(format),
+        _ => from_extension(path),
+    }
+}
+
+#[must_use]
+pub fn is_playable(path: &Path) -> bool {
+    is_candidate(path) && detect(path).is_some_and(|format| format.decodable)
+}
+
+/// Opens `path` for decoding, passing the sniffed format to the probe as a hint.
+pub fn decoder_builder<R>(path: &Path) -> io::Result<DecoderBuilder<R>>
+where
+    R: From<File> + Read + Seek + Send + Sync + 'static,
+{
+    let format = detect(path);
+
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let builder = DecoderBuilder::new()
+        .with_data(R::from(file))
+        .with_byte_len(len);
+
+    with_format(builder, format)
+}
+
+/// Passes `format` to the probe as a hint, refusing formats we have no decoder for.
+pub fn with_format<R>(
+    builder: DecoderBuilder<R>,
+    format: Option<&'static Format>,
+) -> io::Result<DecoderBuilder<R>>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    match format {
+        Some(format) if !format.decodable => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("no decoder available for {}", format.name),
+        )),
+        Some(format) => Ok(builder
+            .with_hint(format.extensions[0])
+            .with_mime_type(format.mime)),
+        None => Ok(builder),
+    }
+}
+
+fn sniff_file(path: &Path) -> io::Result<Option<&'static Format>> {
+    let mut file = File::open(path)?;
+    let mut header = [0u8; SNIFF_LEN];
+    let mut len = read_header(&mut file, &mut header)?;
+
+    // ID3v2 tags get prepended to MP3s and, less politely, to FLAC and AAC files too.
+    let mut start = 0u64;
+    while len >= 10 && &header[..3] == b"ID3" {
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |size, byte| (size << 7) | u64::from(byte & 0x7F));
+        let footer = if header[5] & 0x10 == 0 { 0 } else { 10 };
+
+        start += 10 + size + footer;
+        file.seek(SeekFrom::Start(start))?;
+        len = read_header(&mut file, &mut header)?;
+    }
+
+    Ok(sniff(&header[..len]).or((start > 0).then_some(&MP3)))
+}
+
+fn read_header(file: &mut File, header: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+
+    while len < header.len() {
+        match file.read(&mut header[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+
+    Ok(len)
+}
+
+#[must_use]
+pub fn sniff(header: &[u8]) -> Option<&'static Format> {
+    let at = |offset: usize, magic: &[u8]| header.get(offset..offset + magic.len()) == Some(magic);
+
+    if at(0, b"fLaC") {
+        Some(&FLAC)
+    } else if at(0, b"OggS") {
+        // The first packet starts after the 27 byte page header and its segment table.
+        let packet = 27 + usize::from(*header.get(26)?);
+
+        if at(packet, b"OpusHead") {
+            Some(&OPUS)
+        } else if at(packet, b"\x7FFLAC") {
+            Some(&FLAC)
+        } else {
+            Some(&VORBIS)
+        }
+    } else if (at(0, b"RIFF") || at(0, b"RF64")) && at(8, b"WAVE") {
+        Some(&WAV)
+    } else if at(0, b"FORM") && (at(8, b"AIFF") || at(8, b"AIFC")) {
+        Some(&AIFF)
+    } else if at(0, b"caff") {
+        Some(&CAF)
+    } else if at(4, b"ftyp") {
+        Some(&MP4)
+    } else if at(0, b"\x1A\x45\xDF\xA3") {
+        Some(&MATROSKA)
+    } else if at(0, b"wvpk") {
+        Some(&WAVPACK)
+    } else if at(0, b"MAC ") {
+        Some(&APE)
+    } else if let [0xFF, second, ..] = header
+        && second & 0xE0 == 0xE0
+    {
+        // MPEG frame sync. Layer bits of zero mean an ADTS (AAC) header instead.
+        if second & 0x06 == 0 {
+            Some(&AAC)
+        } else {
+            Some(&MP3)
+        }
+    } else {
+        None
+    }
+}
@@ -0,0 +1,90 @@
+use crate::controller::state::{ReplayGain, ReplayGainMode};
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+pub struct GainControl(AtomicU32);
+
+impl GainControl {
+    #[must_use]
+    pub fn new(factor: f32) -> Self {
+        GainControl(AtomicU32::new(factor.to_bits()))
+    }
+
+    pub fn set(&self, factor: f32) {
+        self.0.store(factor.to_bits(), Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+pub struct Gain<S> {
+    input: S,
+    control: Arc<GainControl>,
+}
+
+impl<S: Source> Gain<S> {
+    pub fn new(input: S, control: Arc<GainControl>) -> Self {
+        Gain { input, control }
+    }
+}
+
+impl<S: Source> Iterator for Gain<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        self.input.next().map(|sample| sample * self.control.get())
+    }
+}
+
+impl<S: Source> Source for Gain<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[must_use]
+pub fn replay_gain_factor(replay_gain: &ReplayGain, mode: ReplayGainMode) -> f32 {
+    let (gain, peak) = match mode {
+        ReplayGainMode::Off => return 1.0,
+        ReplayGainMode::Track => (
+            replay_gain.track_gain.or(replay_gain.album_gain),
+            replay_gain.track_peak.or(replay_gain.album_peak),
+        ),
+        ReplayGainMode::Album => (
+            replay_gain.album_gain.or(replay_gain.track_gain),
+            replay_gain.album_peak.or(replay_gain.track_peak),
+        ),
+    };
+
+    let Some(gain) = gain else {
+        return 1.0;
+    };
+
+    let factor = 10f32.powf(gain / 20.0);
+
+    match peak {
+        Some(peak) if peak > 0.0 => factor.min(1.0 / peak),
+        _ => factor,
+    }
+}
@@ -1,40 +1,185 @@
-use crossbeam_channel::{Receiver, Sender};
-use std::time::Duration;
-use std::{fs::File, path::PathBuf};
+pub mod ab_loop;
+pub mod analysis;
+pub mod eq;
+pub mod fade;
+pub mod formats;
+pub mod gain;
+pub mod output;
+pub mod queued;
+pub mod span;
+pub mod stream;
+pub mod stretch;
 
-use crate::controller::state::PlaybackStatus;
-use crate::controller::state::TrackId;
+use crossbeam_channel::{Receiver, Sender, never, select, tick};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use std::{fs::File, io};
+
+use crate::controller::state::{FadeCurve, PlaybackStatus};
+use crate::controller::state::{ReplayGain, ReplayGainMode, SourceLocation, TrackId, TrackSource};
 use crate::{
-    controller::{commands::AudioCommand, events::AudioEvent},
+    controller::{
+        commands::AudioCommand,
+        events::{AudioErrorKind, AudioEvent},
+    },
     errors::AudioError,
 };
-use rodio::{DeviceSinkBuilder, MixerDeviceSink, Player, decoder::DecoderBuilder};
+use rodio::decoder::DecoderBuilder;
+use rodio::source::{EmptyCallback, SeekError};
+use rodio::{ChannelCount, Decoder, Player, Sample, SampleRate, Source};
+use tracing::warn;
+
+use ab_loop::{AbLoop, LoopControl};
+use analysis::{Analyzer, Tap, TapBuffer};
+use eq::{EqControl, Equalizer};
+use fade::Fader;
+use gain::{Gain, GainControl, replay_gain_factor};
+use output::{DeviceWatcher, Output, list_devices};
+use queued::{Queued, QueuedControl};
+use span::Span;
+use stream::{Live, Stream};
+use stretch::{Position, SpeedControl, Stretch};
+
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(3);
+const DEFAULT_POSITION_INTERVAL: Duration = Duration::from_millis(16);
+// About 60 frames a second, whatever rate positions are asked for at.
+const ANALYSIS_INTERVAL: Duration = Duration::from_millis(16);
+
+type Decoded = Equalizer<Gain<AbLoop<Stretch<Span<Input>>>>>;
+
+// Files decode as they play. Streams decode ahead on a thread of their own, so the output
+// never waits on the network.
+enum Input {
+    File(Decoder<File>),
+    Stream(Live),
+}
+
+// A stream that finished connecting on its own thread, to be loaded as asked.
+struct Opened {
+    token: u64,
+    id: TrackId,
+    source: TrackSource,
+    replay_gain: ReplayGain,
+    autoplay: bool,
+    decoder: Result<Input, AudioError>,
+}
+
+#[derive(Clone)]
+struct Loaded {
+    id: TrackId,
+    source: TrackSource,
+    replay_gain: ReplayGain,
+    position: Arc<Position>,
+    ab_loop: Arc<LoopControl>,
+}
 
 pub struct Audio {
     player: Player,
-    stream_handle: MixerDeviceSink,
+    output: Output,
+
+    preferred_device: Option<String>,
+    devices: Vec<String>,
 
     pub rx: Receiver<AudioCommand>,
     pub tx: Sender<AudioEvent>,
 
-    track_ended: bool,
+    position_interval: Duration,
+    position_tick: Receiver<Instant>,
+    end_token: Arc<AtomicU64>,
+
+    generation: Arc<AtomicU64>,
+    current: Arc<Mutex<Option<Loaded>>>,
+
+    crossfade: Duration,
+    crossfade_curve: FadeCurve,
+    crossfade_tx: Sender<u64>,
+    crossfade_rx: Receiver<u64>,
+    // Gapless transitions, reported from the output thread when the next track starts.
+    transition_tx: Sender<(TrackId, SourceLocation)>,
+    transition_rx: Receiver<(TrackId, SourceLocation)>,
+    fade_out_now: Arc<AtomicBool>,
+    fading: Option<Player>,
+    pending: Option<(Loaded, Decoded)>,
+    // The next track appended to the player for a gapless transition, until it starts.
+    queued: Option<Arc<QueuedControl>>,
+    // Streams connect off the engine thread, so a slow server can't hold up everything else.
+    // Only the stream opened by the latest load gets played.
+    opened_tx: Sender<Opened>,
+    opened_rx: Receiver<Opened>,
+    open_token: u64,
+
+    replay_gain_mode: ReplayGainMode,
+    gains: Vec<(TrackId, ReplayGain, Weak<GainControl>)>,
+
+    eq: Arc<EqControl>,
+
+    speed: f32,
+    preserve_pitch: bool,
+    speed_control: Arc<SpeedControl>,
+
+    ab_loop: Option<(Duration, Duration)>,
+    // Loops that couldn't seek back, reported from the output thread.
+    loop_failed_tx: Sender<SeekError>,
+    loop_failed_rx: Receiver<SeekError>,
+
+    tap: Arc<TapBuffer>,
+    analyzer: Analyzer,
+    analysis_enabled: bool,
+    analysis_tick: Receiver<Instant>,
 }
 
 impl Audio {
-    #[allow(clippy::missing_panics_doc)]
     #[must_use]
     pub fn new() -> (Self, Sender<AudioCommand>, Receiver<AudioEvent>) {
         let (cmd_tx, cmd_rx) = crossbeam_channel::unbounded();
         let (event_tx, event_rx) = crossbeam_channel::unbounded();
-        let stream_handle = DeviceSinkBuilder::open_default_sink().unwrap();
-        let player = Player::connect_new(stream_handle.mixer());
+        let (crossfade_tx, crossfade_rx) = crossbeam_channel::unbounded();
+        let (transition_tx, transition_rx) = crossbeam_channel::unbounded();
+        let (opened_tx, opened_rx) = crossbeam_channel::unbounded();
+        let (loop_failed_tx, loop_failed_rx) = crossbeam_channel::unbounded();
+        let output = Output::open(None);
+        let player = Player::connect_new(output.mixer());
 
         let engine = Audio {
-            stream_handle,
+            output,
             player,
+            preferred_device: None,
+            devices: list_devices(),
             rx: cmd_rx,
             tx: event_tx,
-            track_ended: false,
+            position_interval: DEFAULT_POSITION_INTERVAL,
+            position_tick: never(),
+            end_token: Arc::new(AtomicU64::new(0)),
+            generation: Arc::new(AtomicU64::new(0)),
+            current: Arc::new(Mutex::new(None)),
+            crossfade: Duration::from_secs(0),
+            crossfade_curve: FadeCurve::Linear,
+            crossfade_tx,
+            crossfade_rx,
+            transition_tx,
+            transition_rx,
+            fade_out_now: Arc::new(AtomicBool::new(false)),
+            fading: None,
+            pending: None,
+            queued: None,
+            opened_tx,
+            opened_rx,
+            open_token: 0,
+            replay_gain_mode: ReplayGainMode::Track,
+            gains: Vec::new(),
+            eq: Arc::new(EqControl::new()),
+            speed: 1.0,
+            preserve_pitch: false,
+            speed_control: Arc::new(SpeedControl::new()),
+            ab_loop: None,
+            loop_failed_tx,
+            loop_failed_rx,
+            tap: Arc::new(TapBuffer::new()),
+            analyzer: Analyzer::new(),
+            analysis_enabled: false,
+            analysis_tick: never(),
         };
 
         (engine, cmd_tx, event_rx)
@@ -42,70 +187,689 @@ impl Audio {
 
     #[allow(clippy::missing_errors_doc)]
     pub fn run(&mut self) -> Result<(), AudioError> {
+        let device_watcher = DeviceWatcher::spawn(self.devices.clone(), DEVICE_POLL_INTERVAL);
+
         loop {
-            match self.rx.recv()? {
-                AudioCommand::Load(id, path) => self.load_path(id, path)?,
-                AudioCommand::GetPosition => self.emit_position(),
-                AudioCommand::CheckTrackEnded => self.check_track_ended(),
-                AudioCommand::Play => self.play(),
-                AudioCommand::Pause => self.pause(),
-                AudioCommand::Stop => self.stop(),
-                AudioCommand::SetVolume(v) => self.set_volume(v),
-                AudioCommand::Seek(pos) => self.seek(pos)?,
+            select! {
+                recv(self.rx) -> cmd => match cmd? {
+                    AudioCommand::Load(id, source, replay_gain) => {
+                        let location = source.location.clone();
+
+                        if let Err(e) = self.load_path(id, source, replay_gain, true) {
+                            self.report(Some((id, location)), &e);
+                        }
+                    }
+                    AudioCommand::Cue(id, source, replay_gain, position) => {
+                        // Streams can only start from wherever the broadcast is.
+                        let seekable = !source.is_stream();
+                        let location = source.location.clone();
+
+                        if let Err(e) = self
+                            .load_path(id, source, replay_gain, false)
+                            .and_then(|()| if seekable { self.seek(position) } else { Ok(()) })
+                        {
+                            self.report(Some((id, location)), &e);
+                        }
+                    }
+                    AudioCommand::Preload(id, source, replay_gain) => {
+                        // Not reported: the same failure surfaces when the track gets loaded.
+                        if let Err(e) = self.preload(id, source, replay_gain) {
+                            warn!(error = ?e, "Failed to preload next track");
+                        }
+                    }
+                    AudioCommand::ClearPreload => self.clear_preload(),
+                    AudioCommand::SetPositionInterval(interval) => {
+                        self.set_position_interval(interval);
+                    }
+                    AudioCommand::Play => self.play(),
+                    AudioCommand::Pause => self.pause(),
+                    AudioCommand::Stop => self.stop(),
+                    AudioCommand::SetVolume(v) => self.set_volume(v),
+                    AudioCommand::SetCrossfade(duration, curve) => {
+                        self.set_crossfade(duration, curve);
+                    }
+                    AudioCommand::SetReplayGainMode(mode) => self.set_replay_gain_mode(mode),
+                    AudioCommand::UpdateReplayGain(id, replay_gain) => {
+                        self.update_replay_gain(id, replay_gain);
+                    }
+                    AudioCommand::SetEq(bands) => self.eq.set_bands(bands),
+                    AudioCommand::SetEqEnabled(enabled) => self.eq.set_enabled(enabled),
+                    AudioCommand::SetSpeed(speed) => self.set_speed(speed, self.preserve_pitch),
+                    AudioCommand::SetPreservePitch(preserve) => {
+                        self.set_speed(self.speed, preserve);
+                    }
+                    AudioCommand::SetLoop { start, end } => {
+                        self.set_loop((end > start).then_some((start, end)));
+                    }
+                    AudioCommand::ClearLoop => self.set_loop(None),
+                    AudioCommand::SetAnalysis(enabled) => {
+                        self.analysis_enabled = enabled;
+                        self.update_analysis_tick();
+                    }
+                    AudioCommand::ListDevices => self.emit_devices(),
+                    AudioCommand::SetDevice(name) => {
+                        self.preferred_device = name;
+
+                        if let Err(e) = self.switch_device() {
+                            self.report(self.current_track(), &e);
+                        }
+                    }
+                    AudioCommand::Seek(pos) => {
+                        if let Err(e) = self.seek(pos) {
+                            self.report(self.current_track(), &e);
+                        }
+                    }
+                },
+                recv(self.opened_rx) -> opened => self.on_opened(opened?),
+                recv(self.crossfade_rx) -> generation => self.start_crossfade(generation?),
+                recv(self.transition_rx) -> transition => {
+                    let (id, location) = transition?;
+
+                    self.clear_loop();
+                    let _ = self.tx.send(AudioEvent::TrackTransitioned(id, location));
+                }
+                recv(self.position_tick) -> _ => self.on_position_tick(),
+                recv(self.loop_failed_rx) -> error => {
+                    self.clear_loop();
+                    self.report(self.current_track(), &AudioError::from(error?));
+                }
+                recv(self.analysis_tick) -> _ => self.on_analysis_tick(),
+                recv(device_watcher.rx) -> devices => {
+                    if let Err(e) = self.on_devices_changed(devices?) {
+                        self.report(self.current_track(), &e);
+                    }
+                }
             }
         }
     }
 
-    fn load_path(&mut self, id: TrackId, path: PathBuf) -> Result<(), AudioError> {
-        self.player.stop();
+    fn load_path(
+        &mut self,
+        id: TrackId,
+        track_source: TrackSource,
+        replay_gain: ReplayGain,
+        autoplay: bool,
+    ) -> Result<(), AudioError> {
+        self.open_token += 1;
+
+        let decoder = match &track_source.location {
+            SourceLocation::File(path) => open_file(path)?,
+            SourceLocation::Stream(_) => {
+                self.connect_stream(id, track_source, replay_gain, autoplay);
+                return Ok(());
+            }
+        };
+
+        self.start(id, track_source, replay_gain, decoder, autoplay)
+    }
+
+    // Whatever was playing keeps going until the stream is ready, as it does while a file
+    // gets decoded.
+    fn connect_stream(
+        &self,
+        id: TrackId,
+        track_source: TrackSource,
+        replay_gain: ReplayGain,
+        autoplay: bool,
+    ) {
+        let SourceLocation::Stream(url) = track_source.location.clone() else {
+            return;
+        };
+
+        let token = self.open_token;
+        let tx = self.tx.clone();
+        let opened_tx = self.opened_tx.clone();
+
+        std::thread::spawn(move || {
+            let decoder = open_stream(&url, move |title| {
+                let _ = tx.send(AudioEvent::StreamTitle(id, title));
+            });
+
+            let _ = opened_tx.send(Opened {
+                token,
+                id,
+                source: track_source,
+                replay_gain,
+                autoplay,
+                decoder,
+            });
+        });
+    }
+
+    fn on_opened(&mut self, opened: Opened) {
+        if opened.token != self.open_token {
+            return;
+        }
+
+        let id = opened.id;
+        let location = opened.source.location.clone();
+
+        if let Err(e) = opened.decoder.and_then(|decoder| {
+            self.start(
+                id,
+                opened.source,
+                opened.replay_gain,
+                decoder,
+                opened.autoplay,
+            )
+        }) {
+            self.report(Some((id, location)), &e);
+        }
+    }
+
+    fn start(
+        &mut self,
+        id: TrackId,
+        track_source: TrackSource,
+        replay_gain: ReplayGain,
+        decoder: Input,
+        autoplay: bool,
+    ) -> Result<(), AudioError> {
+        // Decode before touching the player, so a broken file leaves playback as it was.
+        let (loaded, source) = self.decode(id, &track_source, replay_gain, decoder)?;
 
         let prev_vol = self.player.volume();
 
-        self.player = Player::connect_new(self.stream_handle.mixer());
+        let crossfade = autoplay
+            && !self.crossfade.is_zero()
+            && !self.player.empty()
+            && !self.player.is_paused();
 
-        let file = File::open(path.clone())?;
-        let len = file.metadata()?.len();
-        let source = DecoderBuilder::new()
-            .with_data(file)
-            .with_byte_len(len)
-            .with_seekable(true)
-            .build()?;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.clear_preload();
+        self.clear_loop();
 
-        self.player.append(source);
+        let next = self.new_player();
+        if !autoplay {
+            next.pause();
+        }
 
-        self.track_ended = false;
+        if crossfade {
+            self.fade_out_now.store(true, Ordering::Relaxed);
+            self.fading = Some(std::mem::replace(&mut self.player, next));
+        } else {
+            self.player.stop();
+            self.fading = None;
+            self.player = next;
+        }
+
+        self.append_current(source, crossfade);
+        self.append_end_callback();
+        self.set_current(loaded);
 
         self.player.set_volume(prev_vol);
 
-        let _ = self.tx.send(AudioEvent::TrackLoaded(id, path));
+        let _ = self
+            .tx
+            .send(AudioEvent::TrackLoaded(id, track_source.location));
 
-        self.play();
+        if autoplay {
+            self.play();
+        } else {
+            self.pause();
+        }
 
         Ok(())
     }
 
+    fn preload(
+        &mut self,
+        id: TrackId,
+        track_source: TrackSource,
+        replay_gain: ReplayGain,
+    ) -> Result<(), AudioError> {
+        // Streams only connect once they're loaded, and never on this thread.
+        let SourceLocation::File(path) = &track_source.location else {
+            return Ok(());
+        };
+
+        if !self.crossfade.is_zero() {
+            if self.pending.is_none() && !self.player.empty() {
+                let decoder = open_file(path)?;
+                self.pending = Some(self.decode(id, &track_source, replay_gain, decoder)?);
+            }
+
+            return Ok(());
+        }
+
+        if self.player.empty()
+            || self
+                .queued
+                .as_ref()
+                .is_some_and(|queued| queued.is_waiting())
+        {
+            return Ok(());
+        }
+
+        let decoder = open_file(path)?;
+        let (loaded, source) = self.decode(id, &track_source, replay_gain, decoder)?;
+        let location = track_source.location;
+
+        let generation = self.generation.clone();
+        let expected = generation.load(Ordering::Acquire);
+        let current = self.current.clone();
+        let transition_tx = self.transition_tx.clone();
+        let queued = Arc::new(QueuedControl::new());
+        let start = queued.clone();
+
+        self.player.append(EmptyCallback::new(Box::new(move || {
+            if generation.load(Ordering::Acquire) == expected && start.start() {
+                if let Ok(mut current) = current.lock() {
+                    *current = Some(loaded.clone());
+                }
+
+                let _ = transition_tx.send((id, location.clone()));
+            }
+        })));
+        self.player.append(Tap::new(
+            Queued::new(source, queued.clone()),
+            self.tap.clone(),
+        ));
+        self.append_end_callback();
+        self.queued = Some(queued);
+
+        Ok(())
+    }
+
+    // Drops whatever was lined up to play next. A cancelled gapless track still sits in the
+    // player, but plays as if empty, and its end callback reports the current track ending.
+    fn clear_preload(&mut self) {
+        self.pending = None;
+
+        if let Some(queued) = self.queued.take() {
+            queued.cancel();
+        }
+    }
+
+    fn append_current(&mut self, source: Decoded, fade_in: bool) {
+        if self.crossfade.is_zero() {
+            self.player.append(Tap::new(source, self.tap.clone()));
+            return;
+        }
+
+        self.fade_out_now = Arc::new(AtomicBool::new(false));
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let crossfade_tx = self.crossfade_tx.clone();
+
+        let source = Fader::new(
+            source,
+            self.crossfade,
+            self.crossfade_curve,
+            fade_in,
+            self.fade_out_now.clone(),
+            Box::new(move || {
+                let _ = crossfade_tx.send(generation);
+            }),
+        );
+
+        self.player.append(Tap::new(source, self.tap.clone()));
+    }
+
+    fn start_crossfade(&mut self, generation: u64) {
+        if generation != self.generation.load(Ordering::Acquire) {
+            return;
+        }
+
+        let Some((loaded, source)) = self.pending.take() else {
+            return;
+        };
+
+        let prev_vol = self.player.volume();
+
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.clear_loop();
+
+        let next = self.new_player();
+        self.fading = Some(std::mem::replace(&mut self.player, next));
+
+        let (id, location) = (loaded.id, loaded.source.location.clone());
+
+        self.append_current(source, true);
+        self.append_end_callback();
+        self.set_current(loaded);
+        self.player.set_volume(prev_vol);
+
+        let _ = self.tx.send(AudioEvent::TrackTransitioned(id, location));
+    }
+
+    fn append_end_callback(&self) {
+        let token = self.end_token.fetch_add(1, Ordering::AcqRel) + 1;
+        let end_token = self.end_token.clone();
+        let tx = self.tx.clone();
+
+        self.player.append(EmptyCallback::new(Box::new(move || {
+            if end_token.load(Ordering::Acquire) == token {
+                let _ = tx.send(AudioEvent::TrackEnded);
+            }
+        })));
+    }
+
+    fn set_current(&self, loaded: Loaded) {
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(loaded);
+        }
+    }
+
+    fn current_track(&self) -> Option<(TrackId, SourceLocation)> {
+        self.current.lock().ok().and_then(|current| {
+            current
+                .as_ref()
+                .map(|loaded| (loaded.id, loaded.source.location.clone()))
+        })
+    }
+
+    fn report(&self, track: Option<(TrackId, SourceLocation)>, error: &AudioError) {
+        warn!(error = ?error, "Audio error");
+
+        let (track, location) = track.unzip();
+        let _ = self.tx.send(AudioEvent::Error {
+            track,
+            location,
+            kind: error_kind(error),
+        });
+    }
+
+    fn current_position(&self) -> Duration {
+        self.current
+            .lock()
+            .ok()
+            .and_then(|current| current.as_ref().map(|loaded| loaded.position.get()))
+            .unwrap_or_default()
+    }
+
+    fn new_player(&self) -> Player {
+        let player = Player::connect_new(self.output.mixer());
+
+        if !self.preserve_pitch {
+            player.set_speed(self.speed);
+        }
+
+        player
+    }
+
+    fn set_speed(&mut self, speed: f32, preserve_pitch: bool) {
+        self.speed = speed.clamp(0.25, 4.0);
+        self.preserve_pitch = preserve_pitch;
+        self.speed_control.set(self.speed, preserve_pitch);
+
+        let player_speed = if preserve_pitch { 1.0 } else { self.speed };
+
+        self.player.set_speed(player_speed);
+        if let Some(fading) = &self.fading {
+            fading.set_speed(player_speed);
+        }
+    }
+
+    fn emit_devices(&self) {
+        let _ = self.tx.send(AudioEvent::DevicesChanged {
+            devices: self.devices.clone(),
+            current: self.output.name().map(ToString::to_string),
+        });
+    }
+
+    fn on_devices_changed(&mut self, devices: Vec<String>) -> Result<(), AudioError> {
+        if devices == self.devices {
+            return Ok(());
+        }
+
+        self.devices = devices;
+
+        let wanted = self
+            .preferred_device
+            .as_ref()
+            .filter(|name| self.devices.contains(name));
+
+        let lost = self
+            .output
+            .name()
+            .is_some_and(|name| !self.devices.iter().any(|d| d == name));
+
+        let switch = match wanted {
+            Some(name) => self.output.name() != Some(name.as_str()),
+            None => lost || (self.output.is_null() && !self.devices.is_empty()),
+        };
+
+        if switch {
+            self.switch_device()?;
+        } else {
+            self.emit_devices();
+        }
+
+        Ok(())
+    }
+
+    fn switch_device(&mut self) -> Result<(), AudioError> {
+        let position = self.current_position();
+        let paused = self.player.is_paused();
+        let playing = !self.player.empty();
+        let volume = self.player.volume();
+
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.open_token += 1;
+        self.clear_preload();
+        self.fading = None;
+        self.player.stop();
+
+        self.output = Output::open(self.preferred_device.as_deref());
+        self.player = self.new_player();
+        self.player.set_volume(volume);
+
+        let current = self.current.lock().ok().and_then(|current| current.clone());
+
+        if playing && let Some(current) = current {
+            if paused {
+                self.player.pause();
+            }
+
+            match &current.source.location {
+                SourceLocation::File(path) => {
+                    let decoder = open_file(path)?;
+                    let (loaded, source) =
+                        self.decode(current.id, &current.source, current.replay_gain, decoder)?;
+
+                    self.append_current(source, false);
+                    self.append_end_callback();
+                    self.set_current(loaded);
+                    self.player.try_seek(position)?;
+                }
+                // A stream reconnects, and picks up wherever the broadcast is now.
+                SourceLocation::Stream(_) => {
+                    self.connect_stream(current.id, current.source, current.replay_gain, !paused);
+                }
+            }
+        }
+
+        self.emit_devices();
+
+        Ok(())
+    }
+
+    fn set_crossfade(&mut self, duration: Duration, curve: FadeCurve) {
+        // Crossfades and gapless transitions line up the next track differently.
+        if duration.is_zero() != self.crossfade.is_zero() {
+            self.clear_preload();
+        }
+
+        self.crossfade = duration;
+        self.crossfade_curve = curve;
+    }
+
+    fn set_replay_gain_mode(&mut self, mode: ReplayGainMode) {
+        self.replay_gain_mode = mode;
+        self.apply_replay_gain();
+    }
+
+    fn update_replay_gain(&mut self, id: TrackId, replay_gain: ReplayGain) {
+        for (track_id, gain, _) in &mut self.gains {
+            if *track_id == id {
+                *gain = replay_gain;
+            }
+        }
+
+        self.apply_replay_gain();
+    }
+
+    fn apply_replay_gain(&mut self) {
+        self.gains
+            .retain(|(_, _, control)| control.strong_count() > 0);
+
+        for (_, replay_gain, control) in &self.gains {
+            if let Some(control) = control.upgrade() {
+                control.set(replay_gain_factor(replay_gain, self.replay_gain_mode));
+            }
+        }
+    }
+
+    fn decode(
+        &mut self,
+        id: TrackId,
+        track_source: &TrackSource,
+        replay_gain: ReplayGain,
+        decoder: Input,
+    ) -> Result<(Loaded, Decoded), AudioError> {
+        let source = Span::new(decoder, track_source.range)?;
+
+        let control = Arc::new(GainControl::new(replay_gain_factor(
+            &replay_gain,
+            self.replay_gain_mode,
+        )));
+
+        self.gains
+            .retain(|(_, _, control)| control.strong_count() > 0);
+        self.gains.push((id, replay_gain, Arc::downgrade(&control)));
+
+        let position = Arc::new(Position::new());
+        let source = Stretch::new(source, self.speed_control.clone(), position.clone());
+
+        let ab_loop = Arc::new(LoopControl::new());
+        let source = AbLoop::new(
+            source,
+            ab_loop.clone(),
+            position.clone(),
+            self.loop_failed_tx.clone(),
+        );
+
+        let loaded = Loaded {
+            id,
+            source: track_source.clone(),
+            replay_gain,
+            position,
+            ab_loop,
+        };
+
+        Ok((
+            loaded,
+            Equalizer::new(Gain::new(source, control), self.eq.clone()),
+        ))
+    }
+
     fn emit_position(&self) {
         self.tx
-            .send(AudioEvent::Position(self.player.get_pos()))
+            .send(AudioEvent::Position(self.current_position()))
             .ok();
     }
 
-    fn play(&self) {
+    // The current track's source enforces the loop itself, so it ends up exactly on B.
+    fn set_loop(&mut self, ab_loop: Option<(Duration, Duration)>) {
+        self.ab_loop = ab_loop;
+
+        if let Ok(current) = self.current.lock()
+            && let Some(loaded) = current.as_ref()
+        {
+            match ab_loop {
+                Some((start, end)) => loaded.ab_loop.set(start, end),
+                None => loaded.ab_loop.clear(),
+            }
+        }
+    }
+
+    // A loop belongs to the track it was set on, so it goes whenever another one starts.
+    fn clear_loop(&mut self) {
+        if self.ab_loop.is_some() {
+            self.set_loop(None);
+            let _ = self.tx.send(AudioEvent::LoopCleared);
+        }
+    }
+
+    fn set_position_interval(&mut self, interval: Duration) {
+        self.position_interval = interval.max(Duration::from_millis(1));
+
+        if self.is_playing() {
+            self.position_tick = tick(self.position_interval);
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        !self.player.empty() && !self.player.is_paused()
+    }
+
+    fn on_position_tick(&mut self) {
+        if self.fading.as_ref().is_some_and(Player::empty) {
+            self.fading = None;
+        }
+
+        self.emit_position();
+
+        if !self.is_playing() {
+            self.position_tick = never();
+        }
+    }
+
+    fn on_analysis_tick(&mut self) {
+        let analysis = self.analyzer.analyze(&self.tap);
+        let _ = self.tx.send(AudioEvent::Analysis(analysis));
+
+        if !self.is_playing() {
+            self.analysis_tick = never();
+        }
+    }
+
+    fn update_analysis_tick(&mut self) {
+        self.analysis_tick = if self.analysis_enabled && self.is_playing() {
+            tick(ANALYSIS_INTERVAL)
+        } else {
+            never()
+        };
+    }
+
+    fn play(&mut self) {
         self.player.play();
+        if let Some(fading) = &self.fading {
+            fading.play();
+        }
+
+        self.position_tick = tick(self.position_interval);
+        self.update_analysis_tick();
+
         let _ = self
             .tx
             .send(AudioEvent::PlaybackStatus(PlaybackStatus::Playing));
     }
 
-    fn pause(&self) {
+    fn pause(&mut self) {
         self.player.pause();
+        if let Some(fading) = &self.fading {
+            fading.pause();
+        }
+
+        self.position_tick = never();
+        self.analysis_tick = never();
+        self.emit_position();
+
         let _ = self
             .tx
             .send(AudioEvent::PlaybackStatus(PlaybackStatus::Paused));
     }
 
-    fn stop(&self) {
+    fn stop(&mut self) {
+        self.end_token.fetch_add(1, Ordering::AcqRel);
+        self.open_token += 1;
         self.player.stop();
+        self.fading = None;
+        self.position_tick = never();
+        self.analysis_tick = never();
+
         let _ = self
             .tx
             .send(AudioEvent::PlaybackStatus(PlaybackStatus::Stopped));
@@ -114,19 +878,97 @@ impl Audio {
     fn set_volume(&self, volume: f32) {
         let volume = volume.clamp(0.0, 1.0);
         self.player.set_volume(volume);
+        if let Some(fading) = &self.fading {
+            fading.set_volume(volume);
+        }
     }
 
     fn seek(&self, pos: Duration) -> Result<(), AudioError> {
         self.player.try_seek(pos)?;
+        self.emit_position();
 
         Ok(())
     }
+}
+
+impl Iterator for Input {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        match self {
+            Input::File(decoder) => decoder.next(),
+            Input::Stream(live) => live.next(),
+        }
+    }
+}
+
+impl Source for Input {
+    fn current_span_len(&self) -> Option<usize> {
+        match self {
+            Input::File(decoder) => decoder.current_span_len(),
+            Input::Stream(live) => live.current_span_len(),
+        }
+    }
+
+    fn channels(&self) -> ChannelCount {
+        match self {
+            Input::File(decoder) => decoder.channels(),
+            Input::Stream(live) => live.channels(),
+        }
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        match self {
+            Input::File(decoder) => decoder.sample_rate(),
+            Input::Stream(live) => live.sample_rate(),
+        }
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match self {
+            Input::File(decoder) => decoder.total_duration(),
+            Input::Stream(live) => live.total_duration(),
+        }
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        match self {
+            Input::File(decoder) => decoder.try_seek(pos),
+            Input::Stream(live) => live.try_seek(pos),
+        }
+    }
+}
 
-    fn check_track_ended(&mut self) {
-        if self.player.empty() && !self.track_ended {
-            self.track_ended = true;
+fn open_file(path: &Path) -> Result<Input, AudioError> {
+    let decoder = formats::decoder_builder(path)?
+        .with_seekable(true)
+        .build()?;
+
+    Ok(Input::File(decoder))
+}
+
+// Connecting and probing wait on the server, for as long as it takes to answer.
+fn open_stream(
+    url: &str,
+    on_title: impl FnMut(String) + Send + 'static,
+) -> Result<Input, AudioError> {
+    let (stream, format) = Stream::open(url, on_title)?;
+    let builder = DecoderBuilder::new().with_data(stream);
+    let decoder = formats::with_format(builder, format)?.build()?;
+
+    Ok(Input::Stream(Live::new(decoder)))
+}
 
-            let _ = self.tx.send(AudioEvent::TrackEnded);
+fn error_kind(error: &AudioError) -> AudioErrorKind {
+    match error {
+        AudioError::IoError(e) if e.kind() == io::ErrorKind::NotFound => AudioErrorKind::Missing,
+        AudioError::IoError(e) if e.kind() == io::ErrorKind::Unsupported => {
+            AudioErrorKind::Unsupported(e.to_string())
         }
+        AudioError::IoError(e) => AudioErrorKind::Io(e.to_string()),
+        AudioError::RecvError(e) => AudioErrorKind::Io(e.to_string()),
+        AudioError::RodioDecoderError(e) => AudioErrorKind::Decode(e.to_string()),
+        AudioError::LoadFile(e) => AudioErrorKind::Decode(e.clone()),
+        AudioError::SeekError(e) => AudioErrorKind::Seek(e.to_string()),
     }
 }
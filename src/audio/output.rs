@@ -0,0 +1,175 @@
+use crossbeam_channel::Receiver;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::mixer::{self, Mixer};
+use rodio::{ChannelCount, DeviceSinkBuilder, MixerDeviceSink, SampleRate};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+const NULL_CHANNELS: ChannelCount = ChannelCount::new(2).unwrap();
+const NULL_SAMPLE_RATE: SampleRate = SampleRate::new(44_100).unwrap();
+const NULL_TICK: Duration = Duration::from_millis(10);
+
+pub enum Output {
+    Device {
+        sink: MixerDeviceSink,
+        name: Option<String>,
+    },
+    Null {
+        mixer: Mixer,
+        running: Arc<AtomicBool>,
+    },
+}
+
+impl Output {
+    #[must_use]
+    pub fn open(name: Option<&str>) -> Self {
+        if let Some(name) = name {
+            if let Some(output) = Self::open_named(name) {
+                return output;
+            }
+
+            warn!(device = name, "Output device unavailable, using default");
+        }
+
+        let host = rodio::cpal::default_host();
+
+        if let Some(device) = host.default_output_device() {
+            let name = device_name(&device);
+
+            if let Ok(builder) = DeviceSinkBuilder::from_device(device)
+                && let Ok(sink) = builder.open_sink_or_fallback()
+            {
+                return Output::Device { sink, name };
+            }
+        }
+
+        warn!("No output device available, using null sink");
+
+        Self::null()
+    }
+
+    fn open_named(name: &str) -> Option<Self> {
+        let device = rodio::cpal::default_host()
+            .output_devices()
+            .ok()?
+            .find(|device| device_name(device).as_deref() == Some(name))?;
+
+        let sink = DeviceSinkBuilder::from_device(device)
+            .ok()?
+            .open_sink_or_fallback()
+            .ok()?;
+
+        Some(Output::Device {
+            sink,
+            name: Some(name.to_string()),
+        })
+    }
+
+    fn null() -> Self {
+        let (mixer, mut source) = mixer::mixer(NULL_CHANNELS, NULL_SAMPLE_RATE);
+        let running = Arc::new(AtomicBool::new(true));
+
+        let samples_per_tick = (u128::from(NULL_SAMPLE_RATE.get())
+            * u128::from(NULL_CHANNELS.get())
+            * NULL_TICK.as_millis()
+            / 1000) as usize;
+
+        std::thread::spawn({
+            let running = running.clone();
+            move || {
+                while running.load(Ordering::Relaxed) {
+                    source.by_ref().take(samples_per_tick).for_each(drop);
+                    std::thread::sleep(NULL_TICK);
+                }
+            }
+        });
+
+        Output::Null { mixer, running }
+    }
+
+    #[must_use]
+    pub fn mixer(&self) -> &Mixer {
+        match self {
+            Output::Device { sink, .. } => sink.mixer(),
+            Output::Null { mixer, .. } => mixer,
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Output::Device { name, .. } => name.as_deref(),
+            Output::Null { .. } => None,
+        }
+    }
+
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Output::Null { .. })
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        if let Output::Null { running, .. } = self {
+            running.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Polls the output devices from its own thread, since listing them can take a while on
+/// some backends, and sends the list whenever it changes.
+pub struct DeviceWatcher {
+    pub rx: Receiver<Vec<String>>,
+    running: Arc<AtomicBool>,
+}
+
+impl DeviceWatcher {
+    #[must_use]
+    pub fn spawn(mut known: Vec<String>, interval: Duration) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let running = Arc::new(AtomicBool::new(true));
+
+        std::thread::spawn({
+            let running = running.clone();
+            move || {
+                while running.load(Ordering::Relaxed) {
+                    std::thread::sleep(interval);
+
+                    let devices = list_devices();
+
+                    if devices != known {
+                        known.clone_from(&devices);
+
+                        if tx.send(devices).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        DeviceWatcher { rx, running }
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[must_use]
+pub fn list_devices() -> Vec<String> {
+    rodio::cpal::default_host()
+        .output_devices()
+        .map(|devices| devices.filter_map(|device| device_name(&device)).collect())
+        .unwrap_or_default()
+}
+
+#[allow(deprecated)]
+fn device_name(device: &rodio::cpal::Device) -> Option<String> {
+    device.name().ok()
+}
@@ -0,0 +1,92 @@
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+const WAITING: u8 = 0;
+const STARTED: u8 = 1;
+const CANCELLED: u8 = 2;
+
+// Whether a track appended for a gapless transition has started yet. The output thread
+// starts it and the engine cancels it, and whichever comes first wins.
+pub struct QueuedControl(AtomicU8);
+
+impl QueuedControl {
+    #[must_use]
+    pub fn new() -> Self {
+        QueuedControl(AtomicU8::new(WAITING))
+    }
+
+    /// Returns false if it was cancelled first.
+    pub fn start(&self) -> bool {
+        self.0
+            .compare_exchange(WAITING, STARTED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Returns false if it already started.
+    pub fn cancel(&self) -> bool {
+        self.0
+            .compare_exchange(WAITING, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    #[must_use]
+    pub fn is_waiting(&self) -> bool {
+        self.0.load(Ordering::Acquire) == WAITING
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire) == CANCELLED
+    }
+}
+
+// Plays as if it were empty once cancelled, so the player moves straight past it.
+pub struct Queued<S> {
+    input: S,
+    control: Arc<QueuedControl>,
+}
+
+impl<S: Source> Queued<S> {
+    pub fn new(input: S, control: Arc<QueuedControl>) -> Self {
+        Queued { input, control }
+    }
+}
+
+impl<S: Source> Iterator for Queued<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if self.control.is_cancelled() {
+            return None;
+        }
+
+        self.input.next()
+    }
+}
+
+impl<S: Source> Source for Queued<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        if self.control.is_cancelled() {
+            return Some(0);
+        }
+
+        self.input.current_span_len()
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
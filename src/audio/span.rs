@@ -0,0 +1,93 @@
+use crate::controller::state::TrackRange;
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::time::Duration;
+
+// Plays one range of the input as if it were the whole source, for tracks that share a file
+// with others. Positions and seeks are relative to the start of the range.
+pub struct Span<S> {
+    input: S,
+    start: Duration,
+    end: Option<Duration>,
+    length: Option<Duration>,
+    remaining: Option<u64>,
+}
+
+impl<S: Source> Span<S> {
+    pub fn new(mut input: S, range: Option<TrackRange>) -> Result<Self, SeekError> {
+        let (start, end) = range.map_or((Duration::ZERO, None), |range| (range.start, range.end));
+
+        if !start.is_zero() {
+            input.try_seek(start)?;
+        }
+
+        let length = end
+            .or_else(|| input.total_duration())
+            .map(|end| end.saturating_sub(start));
+
+        let mut span = Span {
+            input,
+            start,
+            end,
+            length,
+            remaining: None,
+        };
+        span.remaining = span.samples_until_end(Duration::ZERO);
+
+        Ok(span)
+    }
+
+    fn samples_until_end(&self, pos: Duration) -> Option<u64> {
+        let end = self.end?;
+        let left = end.saturating_sub(self.start + pos);
+
+        let frames = (left.as_secs_f64() * f64::from(self.input.sample_rate().get())) as u64;
+
+        Some(frames * u64::from(self.input.channels().get()))
+    }
+}
+
+impl<S: Source> Iterator for Span<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if let Some(remaining) = &mut self.remaining {
+            if *remaining == 0 {
+                return None;
+            }
+
+            *remaining -= 1;
+        }
+
+        self.input.next()
+    }
+}
+
+impl<S: Source> Source for Span<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        let len = self.input.current_span_len()?;
+
+        Some(match self.remaining {
+            Some(remaining) => len.min(usize::try_from(remaining).unwrap_or(usize::MAX)),
+            None => len,
+        })
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.length
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(self.start + pos)?;
+        self.remaining = self.samples_until_end(pos);
+
+        Ok(())
+    }
+}
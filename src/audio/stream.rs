@@ -0,0 +1,648 @@
+use super::formats::{self, Format};
+use crossbeam_channel::{Receiver, TryRecvError};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::CONTENT_TYPE;
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// How long a read waits on the network before the stream counts as dead.
+#[cfg(not(test))]
+const STALL_TIMEOUT: Duration = Duration::from_secs(15);
+#[cfg(test)]
+const STALL_TIMEOUT: Duration = Duration::from_secs(1);
+const CHUNK_LEN: usize = 16 * 1024;
+// About two seconds of a 128 kbps stream, so a slow start doesn't underrun right away.
+const PREBUFFER_LEN: usize = 32 * 1024;
+// Files served over plain HTTP download much faster than they play, so reading ahead stops
+// here until the decoder catches up.
+const BUFFER_CAP: usize = 4 * 1024 * 1024;
+// Decoded samples are handed to the output thread this many frames at a time.
+const CHUNK_FRAMES: usize = 4096;
+// About three seconds of decoded audio at 44.1 kHz.
+const DECODED_CHUNKS: usize = 32;
+
+/// An HTTP(S) stream, downloaded on a background thread into a buffer the decoder reads
+/// from. Any ICY metadata is stripped out and its titles passed to `on_title`.
+pub struct Stream {
+    shared: Arc<Shared>,
+    position: u64,
+    primed: bool,
+}
+
+struct Shared {
+    buffer: Mutex<Buffer>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct Buffer {
+    data: VecDeque<u8>,
+    ended: bool,
+    error: Option<String>,
+    // Set when the reader is dropped, so the download thread stops.
+    closed: bool,
+}
+
+impl Stream {
+    /// Connects to `url`, returning the stream and the format its content type names. Blocks
+    /// until the server answers, so it's best called off the engine thread.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn open(
+        url: &str,
+        on_title: impl FnMut(String) + Send + 'static,
+    ) -> io::Result<(Self, Option<&'static Format>)> {
+        let response = Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            // Applies to each read rather than the whole body, which never ends.
+            .timeout(STALL_TIMEOUT)
+            .build()
+            .and_then(|client| client.get(url).header("Icy-MetaData", "1").send())
+            .and_then(Response::error_for_status)
+            .map_err(io::Error::other)?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+        };
+
+        let format = header(CONTENT_TYPE.as_str()).and_then(formats::from_mime);
+        let metaint = header("icy-metaint")
+            .and_then(|value| value.parse().ok())
+            .filter(|&metaint| metaint > 0);
+
+        let shared = Arc::new(Shared {
+            buffer: Mutex::new(Buffer::default()),
+            changed: Condvar::new(),
+        });
+
+        let reader = IcyReader::new(response, metaint, on_title);
+
+        std::thread::spawn({
+            let shared = shared.clone();
+            move || download(reader, &shared)
+        });
+
+        Ok((
+            Stream {
+                shared,
+                position: 0,
+                primed: false,
+            },
+            format,
+        ))
+    }
+}
+
+fn download(mut reader: impl Read, shared: &Shared) {
+    let mut chunk = vec![0; CHUNK_LEN];
+
+    loop {
+        let result = reader.read(&mut chunk);
+
+        let Ok(mut buffer) = shared.buffer.lock() else {
+            return;
+        };
+
+        match result {
+            Ok(0) => buffer.ended = true,
+            Ok(n) => buffer.data.extend(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                buffer.ended = true;
+                buffer.error = Some(e.to_string());
+            }
+        }
+
+        shared.changed.notify_all();
+
+        while buffer.data.len() >= BUFFER_CAP && !buffer.closed {
+            buffer = match shared.changed.wait(buffer) {
+                Ok(buffer) => buffer,
+                Err(_) => return,
+            };
+        }
+
+        if buffer.ended || buffer.closed {
+            return;
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> io::Result<MutexGuard<'_, Buffer>> {
+        self.buffer
+            .lock()
+            .map_err(|_| io::Error::other("stream buffer poisoned"))
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let wanted = if self.primed { 1 } else { PREBUFFER_LEN };
+
+        let (mut buffer, wait) = self
+            .shared
+            .changed
+            .wait_timeout_while(self.shared.lock()?, STALL_TIMEOUT, |buffer| {
+                buffer.data.len() < wanted && !buffer.ended
+            })
+            .map_err(|_| io::Error::other("stream buffer poisoned"))?;
+
+        if buffer.data.is_empty() {
+            if wait.timed_out() {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "stream stalled"));
+            }
+
+            return match &buffer.error {
+                Some(error) => Err(io::Error::other(error.clone())),
+                None => Ok(0),
+            };
+        }
+
+        self.primed = true;
+
+        let n = buf.len().min(buffer.data.len());
+        for (dst, src) in buf.iter_mut().zip(buffer.data.drain(..n)) {
+            *dst = src;
+        }
+
+        self.position += n as u64;
+        self.shared.changed.notify_all();
+
+        Ok(n)
+    }
+}
+
+// Probing asks where it is, which is fine; actually moving isn't possible on a live stream.
+impl Seek for Stream {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Current(0) => Ok(self.position),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "can't seek in a network stream",
+            )),
+        }
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        if let Ok(mut buffer) = self.shared.buffer.lock() {
+            buffer.closed = true;
+        }
+
+        self.shared.changed.notify_all();
+    }
+}
+
+/// Plays a stream decoded on a thread of its own, so a stalled network holds up that thread
+/// rather than the output. Playback gets silence until samples arrive again, and ends once
+/// the decoder does.
+pub struct Live {
+    chunks: Receiver<Vec<Sample>>,
+    chunk: std::vec::IntoIter<Sample>,
+    channels: ChannelCount,
+    sample_rate: SampleRate,
+    // Where in the current frame the next sample falls, so a gap never splits one.
+    frame_pos: u16,
+}
+
+impl Live {
+    /// Starts decoding, and waits for the first samples so playback doesn't open on a gap.
+    pub fn new<S: Source + Send + 'static>(mut decoder: S) -> Self {
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+        let (tx, chunks) = crossbeam_channel::bounded(DECODED_CHUNKS);
+
+        std::thread::spawn(move || {
+            let len = CHUNK_FRAMES * usize::from(channels.get());
+
+            loop {
+                let chunk: Vec<Sample> = decoder.by_ref().take(len).collect();
+
+                // Later samples would play at the wrong rate or channel count.
+                let changed =
+                    decoder.channels() != channels || decoder.sample_rate() != sample_rate;
+
+                if chunk.is_empty() || changed || tx.send(chunk).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let chunk = chunks.recv().unwrap_or_default().into_iter();
+
+        Live {
+            chunks,
+            chunk,
+            channels,
+            sample_rate,
+            frame_pos: 0,
+        }
+    }
+}
+
+impl Iterator for Live {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if self.frame_pos == 0 && self.chunk.len() == 0 {
+            match self.chunks.try_recv() {
+                Ok(chunk) => self.chunk = chunk.into_iter(),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => return None,
+            }
+        }
+
+        self.frame_pos = (self.frame_pos + 1) % self.channels.get();
+
+        Some(self.chunk.next().unwrap_or(0.0))
+    }
+}
+
+impl Source for Live {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    fn try_seek(&mut self, _: Duration) -> Result<(), SeekError> {
+        Err(SeekError::NotSupported {
+            underlying_source: std::any::type_name::<Self>(),
+        })
+    }
+}
+
+/// Strips the metadata blocks an Icecast/SHOUTcast server sends after every `metaint`
+/// bytes of audio, reporting each new `StreamTitle` it finds.
+pub struct IcyReader<R, F> {
+    inner: R,
+    metaint: Option<usize>,
+    until_metadata: usize,
+    title: Option<String>,
+    on_title: F,
+}
+
+impl<R: Read, F: FnMut(String)> IcyReader<R, F> {
+    pub fn new(inner: R, metaint: Option<usize>, on_title: F) -> Self {
+        IcyReader {
+            inner,
+            metaint,
+            until_metadata: metaint.unwrap_or_default(),
+            title: None,
+            on_title,
+        }
+    }
+
+    fn read_metadata(&mut self) -> io::Result<()> {
+        let mut len = [0u8; 1];
+        self.inner.read_exact(&mut len)?;
+
+        let mut block = vec![0; usize::from(len[0]) * 16];
+        self.inner.read_exact(&mut block)?;
+
+        if let Some(title) = parse_stream_title(&block)
+            && self.title.as_ref() != Some(&title)
+        {
+            self.title = Some(title.clone());
+            (self.on_title)(title);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read, F: FnMut(String)> Read for IcyReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(metaint) = self.metaint else {
+            return self.inner.read(buf);
+        };
+
+        if self.until_metadata == 0 {
+            self.read_metadata()?;
+            self.until_metadata = metaint;
+        }
+
+        let len = buf.len().min(self.until_metadata);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.until_metadata -= n;
+
+        Ok(n)
+    }
+}
+
+// Blocks look like `StreamTitle='Artist - Title';StreamUrl='';`, padded with NULs.
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    const KEY: &str = "StreamTitle='";
+
+    let text = String::from_utf8_lossy(block);
+    let text = text.trim_end_matches('\0');
+
+    let rest = &text[text.find(KEY)? + KEY.len()..];
+    let title = rest.find("';").map_or(rest, |end| &rest[..end]).trim();
+
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Cursor, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    const METAINT: usize = 16;
+
+    struct Server {
+        url: String,
+        request: mpsc::Receiver<String>,
+        // The connection stays open until this is dropped, like a live stream's would.
+        _hold: mpsc::Sender<()>,
+    }
+
+    // Stands in for an Icecast server: answers a single request with `head` and `body`.
+    fn serve(head: String, body: Vec<u8>) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/live", listener.local_addr().unwrap());
+        let (request_tx, request) = mpsc::channel();
+        let (hold, hang_up) = mpsc::channel::<()>();
+
+        std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(socket.try_clone().unwrap());
+            let mut request = String::new();
+
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+
+                if line.trim().is_empty() {
+                    break;
+                }
+
+                request.push_str(&line.to_ascii_lowercase());
+            }
+
+            request_tx.send(request).unwrap();
+            socket.write_all(head.as_bytes()).unwrap();
+            socket.write_all(&body).unwrap();
+
+            let _ = hang_up.recv();
+        });
+
+        Server {
+            url,
+            request,
+            _hold: hold,
+        }
+    }
+
+    fn metadata_block(title: Option<&str>) -> Vec<u8> {
+        let mut text = title
+            .map(|title| format!("StreamTitle='{title}';StreamUrl='';"))
+            .unwrap_or_default()
+            .into_bytes();
+        text.resize(text.len().div_ceil(16) * 16, 0);
+
+        let mut block = vec![u8::try_from(text.len() / 16).unwrap()];
+        block.extend(text);
+        block
+    }
+
+    // Sends a block after every `METAINT` bytes of audio, the way the server does.
+    fn icy_body(audio: &[u8], title: impl Fn(usize) -> Option<&'static str>) -> Vec<u8> {
+        let mut body = Vec::new();
+
+        for (i, chunk) in audio.chunks(METAINT).enumerate() {
+            body.extend(chunk);
+
+            if chunk.len() == METAINT {
+                body.extend(metadata_block(title(i)));
+            }
+        }
+
+        body
+    }
+
+    fn audio(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn stream_strips_metadata_and_reports_new_titles() {
+        let audio = audio(200);
+        let body = icy_body(&audio, |i| match i {
+            0 | 1 => Some("Artist - One"),
+            2 => None,
+            _ => Some("Artist - Two"),
+        });
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: audio/mpeg\r\nicy-metaint: {METAINT}\r\n\
+             Content-Length: {}\r\n\r\n",
+            body.len()
+        );
+        let server = serve(head, body);
+
+        let (titles_tx, titles) = mpsc::channel();
+        let (mut stream, format) = Stream::open(&server.url, move |title| {
+            titles_tx.send(title).unwrap();
+        })
+        .unwrap();
+
+        let mut read = Vec::new();
+        stream.read_to_end(&mut read).unwrap();
+
+        assert_eq!(format, Some(&formats::MP3));
+        assert_eq!(read, audio);
+        assert_eq!(
+            titles.try_iter().collect::<Vec<_>>(),
+            ["Artist - One", "Artist - Two"]
+        );
+        assert!(server.request.recv().unwrap().contains("icy-metadata: 1"));
+    }
+
+    #[test]
+    fn stalled_stream_times_out() {
+        let head = "HTTP/1.1 200 OK\r\nContent-Type: audio/ogg\r\n\r\n".to_string();
+        let server = serve(head, audio(PREBUFFER_LEN));
+
+        let (mut stream, format) = Stream::open(&server.url, |_| {}).unwrap();
+
+        let mut prebuffer = vec![0; PREBUFFER_LEN];
+        stream.read_exact(&mut prebuffer).unwrap();
+        assert_eq!(format, Some(&formats::VORBIS));
+
+        // Either the download gives up on the connection or the read stops waiting for it.
+        let started = Instant::now();
+        assert!(stream.read(&mut [0; 64]).is_err());
+        assert!(started.elapsed() < STALL_TIMEOUT * 2);
+    }
+
+    // A decoder that only has the samples it's been sent, and waits for more.
+    struct Gated(mpsc::Receiver<Sample>);
+
+    impl Iterator for Gated {
+        type Item = Sample;
+
+        fn next(&mut self) -> Option<Sample> {
+            self.0.recv().ok()
+        }
+    }
+
+    impl Source for Gated {
+        fn current_span_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> ChannelCount {
+            ChannelCount::new(2).unwrap()
+        }
+
+        fn sample_rate(&self) -> SampleRate {
+            SampleRate::new(44_100).unwrap()
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    #[test]
+    fn live_plays_silence_while_the_decoder_waits() {
+        let (samples, gate) = mpsc::channel();
+        let chunk = CHUNK_FRAMES * 2;
+
+        for _ in 0..chunk {
+            samples.send(1.0).unwrap();
+        }
+
+        let mut live = Live::new(Gated(gate));
+
+        let first: Vec<Sample> = live.by_ref().take(chunk).collect();
+        assert_eq!(first, vec![1.0; chunk]);
+
+        let started = Instant::now();
+        let gap: Vec<Sample> = live.by_ref().take(64).collect();
+        assert_eq!(gap, vec![0.0; 64]);
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        for _ in 0..chunk {
+            samples.send(0.5).unwrap();
+        }
+        drop(samples);
+
+        // Whatever silence went out before the samples arrived comes in whole frames.
+        let rest: Vec<Sample> = live.collect();
+        let start = rest.iter().position(|&sample| sample > 0.0).unwrap();
+
+        assert_eq!(start % 2, 0);
+        assert_eq!(rest[start..start + chunk], vec![0.5; chunk]);
+    }
+
+    #[test]
+    fn live_ends_with_the_decoder() {
+        let (samples, gate) = mpsc::channel();
+
+        samples.send(1.0).unwrap();
+        samples.send(1.0).unwrap();
+        drop(samples);
+
+        let live = Live::new(Gated(gate));
+
+        assert_eq!(live.collect::<Vec<_>>(), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn error_status_fails_to_open() {
+        let head = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string();
+        let server = serve(head, Vec::new());
+
+        assert!(Stream::open(&server.url, |_| {}).is_err());
+    }
+
+    #[test]
+    fn icy_reader_handles_reads_that_straddle_blocks() {
+        let audio = audio(100);
+        let body = icy_body(&audio, |_| Some("Artist - Title"));
+
+        let mut titles = Vec::new();
+        let mut reader = IcyReader::new(Cursor::new(body), Some(METAINT), |title| {
+            titles.push(title);
+        });
+
+        let mut read: Vec<u8> = Vec::new();
+        let mut chunk = [0; 7];
+        loop {
+            match reader.read(&mut chunk).unwrap() {
+                0 => break,
+                n => read.extend(&chunk[..n]),
+            }
+        }
+
+        assert_eq!(read, audio);
+        assert_eq!(titles, ["Artist - Title"]);
+    }
+
+    #[test]
+    fn icy_reader_passes_plain_streams_through() {
+        let audio = audio(100);
+        let mut reader = IcyReader::new(Cursor::new(audio.clone()), None, |_| {
+            panic!("no metadata to report");
+        });
+
+        let mut read = Vec::new();
+        reader.read_to_end(&mut read).unwrap();
+
+        assert_eq!(read, audio);
+    }
+
+    #[test]
+    fn stream_title_parsing() {
+        let block = |text: &str| {
+            let mut block = text.as_bytes().to_vec();
+            block.resize(64, 0);
+            block
+        };
+
+        assert_eq!(
+            parse_stream_title(&block("StreamTitle='Artist - Title';StreamUrl='';")),
+            Some("Artist - Title".to_string())
+        );
+        assert_eq!(
+            parse_stream_title(&block("StreamTitle='It's Fine';")),
+            Some("It's Fine".to_string())
+        );
+        assert_eq!(
+            parse_stream_title(&block("StreamTitle='No Terminator")),
+            Some("No Terminator".to_string())
+        );
+        assert_eq!(parse_stream_title(&block("StreamTitle='';")), None);
+        assert_eq!(
+            parse_stream_title(&block("StreamUrl='https://example.com';")),
+            None
+        );
+        assert_eq!(parse_stream_title(&[]), None);
+    }
+}
@@ -0,0 +1,305 @@
+use rodio::{ChannelCount, Sample, SampleRate, Source, source::SeekError};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+const FRAME: Duration = Duration::from_millis(40);
+const SEARCH: Duration = Duration::from_millis(10);
+
+pub struct SpeedControl {
+    speed: AtomicU32,
+    preserve_pitch: AtomicBool,
+}
+
+impl SpeedControl {
+    #[must_use]
+    pub fn new() -> Self {
+        SpeedControl {
+            speed: AtomicU32::new(1f32.to_bits()),
+            preserve_pitch: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set(&self, speed: f32, preserve_pitch: bool) {
+        self.speed.store(speed.to_bits(), Ordering::Relaxed);
+        self.preserve_pitch.store(preserve_pitch, Ordering::Relaxed);
+    }
+
+    fn stretch(&self) -> Option<f32> {
+        let speed = f32::from_bits(self.speed.load(Ordering::Relaxed));
+
+        (self.preserve_pitch.load(Ordering::Relaxed) && (speed - 1.0).abs() > f32::EPSILON)
+            .then_some(speed)
+    }
+}
+
+pub struct Position {
+    frames: AtomicU64,
+    rate: AtomicU32,
+}
+
+impl Position {
+    #[must_use]
+    pub fn new() -> Self {
+        Position {
+            frames: AtomicU64::new(0),
+            rate: AtomicU32::new(1),
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self) -> Duration {
+        let frames = self.frames.load(Ordering::Relaxed);
+        let rate = self.rate.load(Ordering::Relaxed).max(1);
+
+        Duration::from_secs_f64(frames as f64 / f64::from(rate))
+    }
+
+    fn set(&self, frames: u64, rate: u32) {
+        self.frames.store(frames, Ordering::Relaxed);
+        self.rate.store(rate, Ordering::Relaxed);
+    }
+}
+
+// Tracks how far into the decoded input playback has got, and when pitch is preserved
+// time-stretches it with WSOLA so the sink can keep running at its native rate.
+pub struct Stretch<S> {
+    input: S,
+    control: Arc<SpeedControl>,
+    position: Arc<Position>,
+
+    channels: usize,
+    rate: u32,
+    frames_read: u64,
+    sample_in_frame: usize,
+
+    frame_len: usize,
+    search: usize,
+    window: Vec<f32>,
+
+    // Interleaved input, `base` is the absolute frame index of `buffer[0]`.
+    buffer: Vec<f32>,
+    base: u64,
+    nominal: f64,
+    previous: Option<usize>,
+    tail: Vec<f32>,
+    output: VecDeque<f32>,
+    exhausted: bool,
+}
+
+impl<S: Source> Stretch<S> {
+    pub fn new(input: S, control: Arc<SpeedControl>, position: Arc<Position>) -> Self {
+        let channels = usize::from(input.channels().get());
+        let rate = input.sample_rate().get();
+
+        let to_frames = |d: Duration| (d.as_secs_f64() * f64::from(rate)) as usize;
+        let frame_len = to_frames(FRAME).max(2) & !1;
+
+        let window = (0..frame_len)
+            .map(|i| (PI * i as f32 / frame_len as f32).sin().powi(2))
+            .collect();
+
+        position.set(0, rate);
+
+        Stretch {
+            input,
+            control,
+            position,
+            channels,
+            rate,
+            frames_read: 0,
+            sample_in_frame: 0,
+            frame_len,
+            search: to_frames(SEARCH),
+            window,
+            buffer: Vec::new(),
+            base: 0,
+            nominal: 0.0,
+            previous: None,
+            tail: Vec::new(),
+            output: VecDeque::new(),
+            exhausted: false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer.clear();
+        self.base = self.frames_read;
+        self.nominal = 0.0;
+        self.previous = None;
+        self.tail.clear();
+        self.output.clear();
+        self.exhausted = false;
+    }
+
+    fn buffered_frames(&self) -> usize {
+        self.buffer.len() / self.channels
+    }
+
+    fn fill(&mut self, frames: usize) {
+        while !self.exhausted && self.buffered_frames() < frames {
+            for _ in 0..self.channels {
+                let Some(sample) = self.input.next() else {
+                    self.exhausted = true;
+                    break;
+                };
+
+                self.buffer.push(sample);
+            }
+        }
+
+        let whole = self.buffered_frames() * self.channels;
+        self.buffer.truncate(whole);
+    }
+
+    fn correlation(&self, a: usize, b: usize, len: usize) -> f32 {
+        let (a, b) = (a * self.channels, b * self.channels);
+
+        self.buffer[a..a + len * self.channels]
+            .iter()
+            .zip(&self.buffer[b..b + len * self.channels])
+            .map(|(x, y)| x * y)
+            .sum()
+    }
+
+    fn process(&mut self, speed: f32) -> bool {
+        if self.buffer.is_empty() && self.previous.is_none() {
+            self.base = self.frames_read;
+            self.nominal = 0.0;
+        }
+
+        let hop = self.frame_len / 2;
+        let nominal = self.nominal as usize;
+
+        self.fill(nominal + self.search + self.frame_len + hop);
+
+        // Once the input has run out, the window can't move on past its last whole frame, so
+        // all that's left is the tail.
+        let available = self.buffered_frames();
+        if available < self.frame_len || (self.exhausted && nominal + self.frame_len > available) {
+            let flush: Vec<f32> = self.tail.drain(..).collect();
+            self.output.extend(flush);
+            return !self.output.is_empty();
+        }
+
+        let start = match self.previous {
+            None => nominal.min(available - self.frame_len),
+            Some(previous) => {
+                let target = previous + hop;
+                let low = nominal.saturating_sub(self.search);
+                let high = (nominal + self.search).min(available - self.frame_len);
+
+                if target + hop > available || low > high {
+                    nominal.min(available - self.frame_len)
+                } else {
+                    (low..=high)
+                        .step_by(4)
+                        .max_by(|&a, &b| {
+                            self.correlation(a, target, hop)
+                                .total_cmp(&self.correlation(b, target, hop))
+                        })
+                        .unwrap_or(nominal)
+                }
+            }
+        };
+
+        let frame: Vec<f32> = (0..self.frame_len * self.channels)
+            .map(|i| self.buffer[start * self.channels + i] * self.window[i / self.channels])
+            .collect();
+
+        let split = hop * self.channels;
+        for (i, sample) in frame[..split].iter().enumerate() {
+            self.output
+                .push_back(sample + self.tail.get(i).copied().unwrap_or(0.0));
+        }
+        self.tail = frame[split..].to_vec();
+
+        self.previous = Some(start);
+        self.nominal += hop as f64 * f64::from(speed);
+
+        let drop = (self.nominal as usize)
+            .saturating_sub(self.search)
+            .min(start);
+
+        if drop > 0 {
+            self.buffer.drain(..drop * self.channels);
+            self.base += drop as u64;
+            self.nominal -= drop as f64;
+            self.previous = Some(start - drop);
+        }
+
+        self.position
+            .set(self.base + self.nominal as u64, self.rate);
+
+        true
+    }
+}
+
+impl<S: Source> Iterator for Stretch<S> {
+    type Item = Sample;
+
+    fn next(&mut self) -> Option<Sample> {
+        if let Some(speed) = self.control.stretch() {
+            if let Some(sample) = self.output.pop_front() {
+                return Some(sample);
+            }
+
+            if self.process(speed) {
+                return self.output.pop_front();
+            }
+
+            return None;
+        }
+
+        if !self.buffer.is_empty() || !self.output.is_empty() {
+            if let Some(sample) = self.output.pop_front() {
+                return Some(sample);
+            }
+
+            self.frames_read = self.base + self.buffered_frames() as u64;
+            self.reset();
+        }
+
+        let sample = self.input.next()?;
+
+        self.sample_in_frame += 1;
+        if self.sample_in_frame >= self.channels {
+            self.sample_in_frame = 0;
+            self.frames_read += 1;
+            self.position.set(self.frames_read, self.rate);
+        }
+
+        Some(sample)
+    }
+}
+
+impl<S: Source> Source for Stretch<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+
+        self.frames_read = (pos.as_secs_f64() * f64::from(self.rate)) as u64;
+        self.sample_in_frame = 0;
+        self.reset();
+        self.position.set(self.frames_read, self.rate);
+
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@ use std::{
     time::Duration,
 };
 
-use crossbeam_channel::{Receiver, select, tick};
+use crossbeam_channel::{Receiver, Sender, select, tick};
 use gpui::RenderImage;
 use image::Frame;
 use smallvec::smallvec;
@@ -15,6 +15,7 @@ use tracing::error;
 use walkdir::WalkDir;
 
 use crate::{
+    app::spawn_pool_worker,
     cacher::{CacheJob, CachedImage, ImageKind},
     controller::{events::CacherEvent, state::ImageId},
     errors::CacherError,
@@ -84,7 +85,12 @@ impl Cacher {
         }
     }
 
-    pub(super) fn spawn_thumbnail_workers(&self, rx: &Receiver<CacheJob>, workers: usize) {
+    pub(super) fn spawn_thumbnail_workers(
+        &self,
+        rx: &Receiver<CacheJob>,
+        workers: usize,
+        crashed: &Sender<String>,
+    ) {
         let ticker = tick(Duration::from_millis(128));
 
         for _ in 0..workers {
@@ -92,7 +98,7 @@ impl Cacher {
             let ticker = ticker.clone();
             let thumb_rx = rx.clone();
 
-            std::thread::spawn(move || {
+            spawn_pool_worker("cacher thumbnails", crashed, move || {
                 let mut batch = HashMap::with_capacity(16);
                 let mut missing = Vec::new();
 
@@ -127,7 +133,10 @@ impl Cacher {
                                         }
                                     }
                                 }
-                                _ => {}
+                                Ok(_) => {}
+                                // The engine is gone, e.g. crashed and being restarted
+                                // with a fresh pool.
+                                Err(_) => break,
                             }
                         }
 
@@ -146,10 +155,10 @@ impl Cacher {
         }
     }
 
-    pub(super) fn spawn_album_art_worker(&self, rx: Receiver<CacheJob>) {
+    pub(super) fn spawn_album_art_worker(&self, rx: Receiver<CacheJob>, crashed: &Sender<String>) {
         let cacher = Arc::new(self.clone());
 
-        std::thread::spawn(move || {
+        spawn_pool_worker("cacher album art", crashed, move || {
             while let Ok(job) = rx.recv() {
                 match job {
                     CacheJob::LoadAlbumArt(id) => {
@@ -191,10 +200,14 @@ impl Cacher {
         });
     }
 
-    pub(super) fn spawn_playlist_thumbnail_worker(&self, rx: Receiver<CacheJob>) {
+    pub(super) fn spawn_playlist_thumbnail_worker(
+        &self,
+        rx: Receiver<CacheJob>,
+        crashed: &Sender<String>,
+    ) {
         let cacher = Arc::new(self.clone());
 
-        std::thread::spawn(move || {
+        spawn_pool_worker("cacher playlist thumbnails", crashed, move || {
             while let Ok(job) = rx.recv() {
                 match job {
                     CacheJob::LoadPlaylistThumbnail(id) => {
@@ -1,5 +1,8 @@
 use crate::controller::state::ImageId;
-use crate::controller::state::{AppState, LibraryState, PlaybackState, QueueState};
+use crate::controller::state::{
+    AlarmState, AppState, BookmarkState, EqualizerState, LibraryState, PlaybackState, QueueState,
+    ResumeState,
+};
 use crate::errors::CacherError;
 use bitcode::{Decode, Encode};
 use ron::ser::PrettyConfig;
@@ -8,15 +11,31 @@ use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use super::legacy::{LegacyLibraryState, LegacyQueueState};
+use super::migrate::{back_up, read_migrated};
 use super::schema::{
-    CacheFile, CachedLibraryState, CachedPlaybackState, CachedQueueState, ImageKind,
+    CacheFile, CachedAlarmState, CachedBookmarkState, CachedEqualizerState, CachedLibraryState,
+    CachedPlaybackState, CachedQueueState, CachedResumeState, ImageKind,
 };
 
+pub const LIBRARY_CACHE_VERSION: u32 = 6;
+pub const QUEUE_CACHE_VERSION: u32 = 1;
+pub const BOOKMARKS_CACHE_VERSION: u32 = 1;
+pub const RESUME_CACHE_VERSION: u32 = 1;
+// Before version 2 the scan record was a bare map with sources' ages in place of their
+// modification times.
+pub const SCAN_RECORD_VERSION: u32 = 2;
+pub const LOUDNESS_FAILURES_VERSION: u32 = 1;
+
 #[derive(Clone)]
 pub enum CacheJob {
     WriteLibraryState(LibraryState),
     WritePlaybackState(PlaybackState),
     WriteQueueState(QueueState),
+    WriteEqualizerState(EqualizerState),
+    WriteBookmarks(BookmarkState),
+    WriteResumePositions(ResumeState),
+    WriteAlarm(AlarmState),
     WriteImage {
         id: ImageId,
         kind: ImageKind,
@@ -33,12 +52,10 @@ pub enum CacheJob {
 pub fn write_cache<T: Encode>(
     tmp: &PathBuf,
     final_path: &PathBuf,
+    version: u32,
     payload: T,
 ) -> Result<(), CacherError> {
-    let wrapped = CacheFile {
-        version: 1,
-        payload,
-    };
+    let wrapped = CacheFile { version, payload };
 
     let bytes = bitcode::encode(&wrapped);
 
@@ -53,7 +70,7 @@ pub fn write_cache<T: Encode>(
     Ok(())
 }
 
-pub fn read_cache<T>(path: &PathBuf) -> Result<Option<T>, CacherError>
+pub fn read_cache<T>(path: &Path, version: u32) -> Result<Option<T>, CacherError>
 where
     T: for<'a> Decode<'a>,
 {
@@ -65,7 +82,7 @@ where
 
     let file: CacheFile<T> = bitcode::decode(&bytes)?;
 
-    if file.version != 1 {
+    if file.version != version {
         return Ok(None);
     }
 
@@ -81,7 +98,7 @@ pub fn write_library_state_to_disk(
 
     let library = CachedLibraryState::from(state);
 
-    write_cache(&tmp_path, &final_path, library)?;
+    write_cache(&tmp_path, &final_path, LIBRARY_CACHE_VERSION, library)?;
 
     Ok(())
 }
@@ -108,13 +125,79 @@ pub fn write_playback_state_to_disk(
     Ok(())
 }
 
+pub fn write_equalizer_state_to_disk(
+    config_dir: &Path,
+    state: &EqualizerState,
+) -> Result<(), CacherError> {
+    let tmp_path = config_dir.join("equalizer.tmp");
+    let final_path = config_dir.join("equalizer.ron");
+
+    let payload = CachedEqualizerState::from(state);
+
+    let ron = ron::ser::to_string_pretty(&payload, PrettyConfig::default())?;
+
+    {
+        let mut file = fs::File::create(tmp_path.clone())?;
+        file.write_all(ron.as_bytes())?;
+        file.sync_all()?;
+    }
+
+    fs::rename(tmp_path, final_path)?;
+
+    Ok(())
+}
+
+pub fn write_alarm_to_disk(config_dir: &Path, state: &AlarmState) -> Result<(), CacherError> {
+    let tmp_path = config_dir.join("alarm.tmp");
+    let final_path = config_dir.join("alarm.ron");
+
+    let payload = CachedAlarmState::from(state);
+
+    let ron = ron::ser::to_string_pretty(&payload, PrettyConfig::default())?;
+
+    {
+        let mut file = fs::File::create(tmp_path.clone())?;
+        file.write_all(ron.as_bytes())?;
+        file.sync_all()?;
+    }
+
+    fs::rename(tmp_path, final_path)?;
+
+    Ok(())
+}
+
 pub fn write_queue_state_to_disk(cache_dir: &Path, state: &QueueState) -> Result<(), CacherError> {
     let tmp_path = cache_dir.join("queue.tmp");
     let final_path = cache_dir.join("queue.bin");
 
     let queue = CachedQueueState::from(state);
 
-    write_cache(&tmp_path, &final_path, queue)?;
+    write_cache(&tmp_path, &final_path, QUEUE_CACHE_VERSION, queue)?;
+
+    Ok(())
+}
+
+pub fn write_bookmarks_to_disk(cache_dir: &Path, state: &BookmarkState) -> Result<(), CacherError> {
+    let tmp_path = cache_dir.join("bookmarks.tmp");
+    let final_path = cache_dir.join("bookmarks.bin");
+
+    let bookmarks = CachedBookmarkState::from(state);
+
+    write_cache(&tmp_path, &final_path, BOOKMARKS_CACHE_VERSION, bookmarks)?;
+
+    Ok(())
+}
+
+pub fn write_resume_positions_to_disk(
+    cache_dir: &Path,
+    state: &ResumeState,
+) -> Result<(), CacherError> {
+    let tmp_path = cache_dir.join("resume.tmp");
+    let final_path = cache_dir.join("resume.bin");
+
+    let resume = CachedResumeState::from(state);
+
+    write_cache(&tmp_path, &final_path, RESUME_CACHE_VERSION, resume)?;
 
     Ok(())
 }
@@ -122,11 +205,7 @@ pub fn write_queue_state_to_disk(cache_dir: &Path, state: &QueueState) -> Result
 pub fn read_library_state_from_disk(cache_dir: &Path) -> Result<LibraryState, CacherError> {
     let path = cache_dir.join("library.bin");
 
-    if !path.exists() {
-        return Ok(LibraryState::default());
-    }
-
-    match read_cache::<CachedLibraryState>(&path)? {
+    match read_migrated::<LegacyLibraryState>(&path, LIBRARY_CACHE_VERSION)? {
         Some(cached_state) => Ok(LibraryState::from(cached_state)),
         None => Ok(LibraryState::default()),
     }
@@ -135,13 +214,35 @@ pub fn read_library_state_from_disk(cache_dir: &Path) -> Result<LibraryState, Ca
 pub fn read_queue_state_from_disk(cache_dir: &Path) -> Result<QueueState, CacherError> {
     let path = cache_dir.join("queue.bin");
 
+    match read_migrated::<LegacyQueueState>(&path, QUEUE_CACHE_VERSION)? {
+        Some(cached_state) => Ok(QueueState::from(cached_state)),
+        None => Ok(QueueState::default()),
+    }
+}
+
+pub fn read_bookmarks_from_disk(cache_dir: &Path) -> Result<BookmarkState, CacherError> {
+    let path = cache_dir.join("bookmarks.bin");
+
     if !path.exists() {
-        return Ok(QueueState::default());
+        return Ok(BookmarkState::default());
     }
 
-    match read_cache::<CachedQueueState>(&path)? {
-        Some(cached_state) => Ok(QueueState::from(cached_state)),
-        None => Ok(QueueState::default()),
+    match read_cache::<CachedBookmarkState>(&path, BOOKMARKS_CACHE_VERSION)? {
+        Some(cached_state) => Ok(BookmarkState::from(cached_state)),
+        None => Ok(BookmarkState::default()),
+    }
+}
+
+pub fn read_resume_positions_from_disk(cache_dir: &Path) -> Result<ResumeState, CacherError> {
+    let path = cache_dir.join("resume.bin");
+
+    if !path.exists() {
+        return Ok(ResumeState::default());
+    }
+
+    match read_cache::<CachedResumeState>(&path, RESUME_CACHE_VERSION)? {
+        Some(cached_state) => Ok(ResumeState::from(cached_state)),
+        None => Ok(ResumeState::default()),
     }
 }
 
@@ -158,14 +259,77 @@ pub fn read_playback_state_from_disk(cache_dir: &Path) -> Result<PlaybackState,
     Ok(cached.into())
 }
 
-pub fn load_app_state(cache_dir: &Path) -> Result<AppState, CacherError> {
+pub fn read_equalizer_state_from_disk(config_dir: &Path) -> Result<EqualizerState, CacherError> {
+    let path = config_dir.join("equalizer.ron");
+
+    if !path.exists() {
+        return Ok(EqualizerState::default());
+    }
+
+    let ron = fs::read_to_string(path)?;
+    let cached: CachedEqualizerState = ron::de::from_str(&ron)?;
+
+    Ok(cached.into())
+}
+
+pub fn read_alarm_from_disk(config_dir: &Path) -> Result<AlarmState, CacherError> {
+    let path = config_dir.join("alarm.ron");
+
+    if !path.exists() {
+        return Ok(AlarmState::default());
+    }
+
+    let ron = fs::read_to_string(path)?;
+    let cached: CachedAlarmState = ron::de::from_str(&ron)?;
+
+    Ok(cached.into())
+}
+
+pub fn load_app_state(cache_dir: &Path, config_dir: &Path) -> Result<AppState, CacherError> {
     let playback = read_playback_state_from_disk(cache_dir)?;
     let library = read_library_state_from_disk(cache_dir)?;
     let queue = read_queue_state_from_disk(cache_dir)?;
+    let equalizer = or_default(
+        &config_dir.join("equalizer.ron"),
+        read_equalizer_state_from_disk(config_dir),
+    );
+    let bookmarks = or_default(
+        &cache_dir.join("bookmarks.bin"),
+        read_bookmarks_from_disk(cache_dir),
+    );
+    let resume = or_default(
+        &cache_dir.join("resume.bin"),
+        read_resume_positions_from_disk(cache_dir),
+    );
+    let alarm = or_default(
+        &config_dir.join("alarm.ron"),
+        read_alarm_from_disk(config_dir),
+    );
 
     Ok(AppState {
         playback,
         library,
         queue,
+        equalizer,
+        bookmarks,
+        resume,
+        alarm,
+    })
+}
+
+// A file only one feature depends on shouldn't keep the rest of the state from loading, so
+// a broken one is set aside and that feature starts over.
+fn or_default<T: Default>(path: &Path, result: Result<T, CacherError>) -> T {
+    result.unwrap_or_else(|error| {
+        match back_up(path, "unreadable") {
+            Ok(backup) => {
+                tracing::warn!(path = %path.display(), backup = %backup.display(), %error, "Unreadable cache file");
+            }
+            Err(backup_error) => {
+                tracing::warn!(path = %path.display(), %error, %backup_error, "Unreadable cache file");
+            }
+        }
+
+        T::default()
     })
 }
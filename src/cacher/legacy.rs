@@ -0,0 +1,437 @@
+use super::io::read_cache;
+use super::migrate::{Legacy, Upgrade};
+use super::schema::{
+    CachedAudioProperties, CachedChapter, CachedLibraryState, CachedPlaylist, CachedQueueState,
+    CachedReplayGain, CachedScanEntry, CachedScanRecord, CachedSourceLocation, CachedTrack,
+    CachedTrackRange, CachedTrackSource, CachedTrackTags,
+};
+use crate::controller::state::TrackSource;
+use bitcode::{Decode, Encode};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Default, Hash, Eq, Encode, Decode)]
+pub struct CachedTrackSourceV1 {
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Hash, Eq, Encode, Decode)]
+pub struct CachedTrackSourceV2 {
+    pub path: String,
+    pub size: u64,
+    pub modified: u64,
+    pub range: Option<CachedTrackRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedTrackV1 {
+    pub id: [u8; 16],
+    pub sources: Vec<CachedTrackSourceV1>,
+
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+
+    pub duration: u64,
+
+    pub image_id: Option<[u8; 16]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedLibraryStateV1 {
+    pub tracks: HashMap<[u8; 16], CachedTrackV1>,
+    pub playlists: HashMap<String, CachedPlaylist>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedTrackV2 {
+    pub id: [u8; 16],
+    pub sources: Vec<CachedTrackSourceV1>,
+
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+
+    pub duration: u64,
+
+    pub replay_gain: CachedReplayGain,
+
+    pub image_id: Option<[u8; 16]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedLibraryStateV2 {
+    pub tracks: HashMap<[u8; 16], CachedTrackV2>,
+    pub playlists: HashMap<String, CachedPlaylist>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedTrackV3 {
+    pub id: [u8; 16],
+    pub sources: Vec<CachedTrackSourceV2>,
+
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+
+    pub duration: u64,
+
+    pub replay_gain: CachedReplayGain,
+
+    pub image_id: Option<[u8; 16]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedLibraryStateV3 {
+    pub tracks: HashMap<[u8; 16], CachedTrackV3>,
+    pub playlists: HashMap<String, CachedPlaylist>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedTrackV4 {
+    pub id: [u8; 16],
+    pub sources: Vec<CachedTrackSourceV2>,
+
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+
+    pub duration: u64,
+
+    pub replay_gain: CachedReplayGain,
+
+    pub chapters: Vec<CachedChapter>,
+
+    pub image_id: Option<[u8; 16]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedLibraryStateV4 {
+    pub tracks: HashMap<[u8; 16], CachedTrackV4>,
+    pub playlists: HashMap<String, CachedPlaylist>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedTrackV5 {
+    pub id: [u8; 16],
+    pub sources: Vec<CachedTrackSource>,
+
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+
+    pub duration: u64,
+
+    pub replay_gain: CachedReplayGain,
+
+    pub chapters: Vec<CachedChapter>,
+
+    pub image_id: Option<[u8; 16]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedLibraryStateV5 {
+    pub tracks: HashMap<[u8; 16], CachedTrackV5>,
+    pub playlists: HashMap<String, CachedPlaylist>,
+}
+
+impl From<CachedTrackSourceV1> for CachedTrackSourceV2 {
+    fn from(c: CachedTrackSourceV1) -> Self {
+        CachedTrackSourceV2 {
+            path: c.path,
+            size: c.size,
+            modified: c.modified,
+            range: None,
+        }
+    }
+}
+
+impl From<CachedTrackV1> for CachedTrackV2 {
+    fn from(c: CachedTrackV1) -> Self {
+        CachedTrackV2 {
+            id: c.id,
+            sources: c.sources,
+            title: c.title,
+            artist: c.artist,
+            album: c.album,
+            duration: c.duration,
+            replay_gain: CachedReplayGain::default(),
+            image_id: c.image_id,
+        }
+    }
+}
+
+impl From<CachedLibraryStateV1> for CachedLibraryStateV2 {
+    fn from(c: CachedLibraryStateV1) -> Self {
+        CachedLibraryStateV2 {
+            tracks: c
+                .tracks
+                .into_iter()
+                .map(|(id, track)| (id, track.into()))
+                .collect(),
+            playlists: c.playlists,
+        }
+    }
+}
+
+impl From<CachedTrackV2> for CachedTrackV3 {
+    fn from(c: CachedTrackV2) -> Self {
+        CachedTrackV3 {
+            id: c.id,
+            sources: c.sources.into_iter().map(Into::into).collect(),
+            title: c.title,
+            artist: c.artist,
+            album: c.album,
+            duration: c.duration,
+            replay_gain: c.replay_gain,
+            image_id: c.image_id,
+        }
+    }
+}
+
+impl From<CachedLibraryStateV2> for CachedLibraryStateV3 {
+    fn from(c: CachedLibraryStateV2) -> Self {
+        CachedLibraryStateV3 {
+            tracks: c
+                .tracks
+                .into_iter()
+                .map(|(id, track)| (id, track.into()))
+                .collect(),
+            playlists: c.playlists,
+        }
+    }
+}
+
+impl From<CachedTrackV3> for CachedTrackV4 {
+    fn from(c: CachedTrackV3) -> Self {
+        CachedTrackV4 {
+            id: c.id,
+            sources: c.sources,
+            title: c.title,
+            artist: c.artist,
+            album: c.album,
+            duration: c.duration,
+            replay_gain: c.replay_gain,
+            chapters: Vec::new(),
+            image_id: c.image_id,
+        }
+    }
+}
+
+impl From<CachedLibraryStateV3> for CachedLibraryStateV4 {
+    fn from(c: CachedLibraryStateV3) -> Self {
+        CachedLibraryStateV4 {
+            tracks: c
+                .tracks
+                .into_iter()
+                .map(|(id, track)| (id, track.into()))
+                .collect(),
+            playlists: c.playlists,
+        }
+    }
+}
+
+impl From<CachedTrackSourceV2> for CachedTrackSource {
+    fn from(c: CachedTrackSourceV2) -> Self {
+        CachedTrackSource {
+            location: CachedSourceLocation::File(c.path),
+            size: c.size,
+            modified: c.modified,
+            range: c.range,
+        }
+    }
+}
+
+impl From<CachedTrackV4> for CachedTrackV5 {
+    fn from(c: CachedTrackV4) -> Self {
+        CachedTrackV5 {
+            id: c.id,
+            sources: c.sources.into_iter().map(Into::into).collect(),
+            title: c.title,
+            artist: c.artist,
+            album: c.album,
+            duration: c.duration,
+            replay_gain: c.replay_gain,
+            chapters: c.chapters,
+            image_id: c.image_id,
+        }
+    }
+}
+
+impl From<CachedLibraryStateV4> for CachedLibraryStateV5 {
+    fn from(c: CachedLibraryStateV4) -> Self {
+        CachedLibraryStateV5 {
+            tracks: c
+                .tracks
+                .into_iter()
+                .map(|(id, track)| (id, track.into()))
+                .collect(),
+            playlists: c.playlists,
+        }
+    }
+}
+
+impl From<CachedTrackV5> for CachedTrack {
+    fn from(c: CachedTrackV5) -> Self {
+        CachedTrack {
+            id: c.id,
+            sources: c.sources,
+            title: c.title,
+            artist: c.artist,
+            album: c.album,
+            duration: c.duration,
+            tags: CachedTrackTags::default(),
+            properties: CachedAudioProperties::default(),
+            replay_gain: c.replay_gain,
+            chapters: c.chapters,
+            image_id: c.image_id,
+        }
+    }
+}
+
+impl From<CachedLibraryStateV5> for CachedLibraryState {
+    fn from(c: CachedLibraryStateV5) -> Self {
+        CachedLibraryState {
+            tracks: c
+                .tracks
+                .into_iter()
+                .map(|(id, track)| (id, track.into()))
+                .collect(),
+            playlists: c.playlists,
+        }
+    }
+}
+
+pub enum LegacyLibraryState {
+    V1(CachedLibraryStateV1),
+    V2(CachedLibraryStateV2),
+    V3(CachedLibraryStateV3),
+    V4(CachedLibraryStateV4),
+    V5(CachedLibraryStateV5),
+}
+
+impl Legacy for LegacyLibraryState {
+    type Current = CachedLibraryState;
+
+    fn read(path: &Path) -> Option<Self> {
+        if let Ok(Some(v5)) = read_cache(path, 5) {
+            return Some(Self::V5(v5));
+        }
+
+        if let Ok(Some(v4)) = read_cache(path, 4) {
+            return Some(Self::V4(v4));
+        }
+
+        if let Ok(Some(v3)) = read_cache(path, 3) {
+            return Some(Self::V3(v3));
+        }
+
+        if let Ok(Some(v2)) = read_cache(path, 2) {
+            return Some(Self::V2(v2));
+        }
+
+        read_cache(path, 1).ok().flatten().map(Self::V1)
+    }
+
+    fn version(&self) -> u32 {
+        match self {
+            Self::V1(_) => 1,
+            Self::V2(_) => 2,
+            Self::V3(_) => 3,
+            Self::V4(_) => 4,
+            Self::V5(_) => 5,
+        }
+    }
+
+    fn upgrade(self) -> Upgrade<Self, CachedLibraryState> {
+        match self {
+            Self::V1(c) => Upgrade::Legacy(Self::V2(c.into())),
+            Self::V2(c) => Upgrade::Legacy(Self::V3(c.into())),
+            Self::V3(c) => Upgrade::Legacy(Self::V4(c.into())),
+            Self::V4(c) => Upgrade::Legacy(Self::V5(c.into())),
+            Self::V5(c) => Upgrade::Current(c.into()),
+        }
+    }
+}
+
+// The queue is still on its first layout.
+pub enum LegacyQueueState {}
+
+impl Legacy for LegacyQueueState {
+    type Current = CachedQueueState;
+
+    fn read(_: &Path) -> Option<Self> {
+        None
+    }
+
+    fn version(&self) -> u32 {
+        match *self {}
+    }
+
+    fn upgrade(self) -> Upgrade<Self, CachedQueueState> {
+        match self {}
+    }
+}
+
+// The first scan record was a bare map, without the `CacheFile` wrapper, keyed on the
+// sources as the library stored them then.
+pub type CachedScanRecordV1 = HashMap<CachedTrackSourceV1, [u8; 16]>;
+
+pub enum LegacyScanRecord {
+    V1(CachedScanRecordV1),
+}
+
+impl Legacy for LegacyScanRecord {
+    type Current = CachedScanRecord;
+
+    fn read(path: &Path) -> Option<Self> {
+        let bytes = std::fs::read(path).ok()?;
+
+        bitcode::decode(&bytes).ok().map(Self::V1)
+    }
+
+    fn version(&self) -> u32 {
+        match self {
+            Self::V1(_) => 1,
+        }
+    }
+
+    fn upgrade(self) -> Upgrade<Self, CachedScanRecord> {
+        match self {
+            Self::V1(c) => Upgrade::Current(upgrade_scan_record_v1(&c)),
+        }
+    }
+}
+
+// The first scan records kept each file's age at the time they were written instead of its
+// modification time, which leaves only the size to go on. Files still matching it are
+// taken as they are now; the rest are read again on the next scan.
+fn upgrade_scan_record_v1(raw: &CachedScanRecordV1) -> CachedScanRecord {
+    raw.iter()
+        .filter_map(|(cached, id)| {
+            let fresh = TrackSource::generate(Path::new(&cached.path))
+                .ok()?
+                .with_content_hash();
+
+            if fresh.size != cached.size {
+                return None;
+            }
+
+            let source = CachedTrackSource {
+                location: CachedSourceLocation::File(cached.path.clone()),
+                size: fresh.size,
+                modified: fresh.modified,
+                range: None,
+            };
+
+            let entry = CachedScanEntry {
+                id: *id,
+                content_hash: fresh.content_hash.map(u128::to_le_bytes),
+            };
+
+            Some((source, entry))
+        })
+        .collect()
+}
@@ -0,0 +1,266 @@
+use crate::errors::CacherError;
+use bitcode::{Decode, Encode};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::io::{read_cache, write_cache};
+
+/// Where one step of a migration leaves a cache file.
+pub enum Upgrade<L, T> {
+    Legacy(L),
+    Current(T),
+}
+
+/// The older layouts of one cache file, each of which knows how to become the one after it.
+pub trait Legacy: Sized {
+    type Current;
+
+    /// Decodes the file as whichever older layout it was written in.
+    fn read(path: &Path) -> Option<Self>;
+
+    fn version(&self) -> u32;
+
+    /// Moves up exactly one version.
+    fn upgrade(self) -> Upgrade<Self, Self::Current>;
+}
+
+/// Reads a versioned cache file, migrating an older one up to `version` a step at a time
+/// and writing the result back. The old file is copied aside first, and so is a file
+/// nothing can read, so neither is lost the next time the cache gets written.
+#[allow(clippy::missing_errors_doc)]
+pub fn read_migrated<L>(path: &PathBuf, version: u32) -> Result<Option<L::Current>, CacherError>
+where
+    L: Legacy,
+    L::Current: for<'a> Decode<'a> + Encode + Clone,
+{
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    if let Ok(Some(current)) = read_cache::<L::Current>(path, version) {
+        return Ok(Some(current));
+    }
+
+    let Some(mut legacy) = L::read(path) else {
+        let backup = back_up(path, "unreadable")?;
+        tracing::warn!(path = %path.display(), backup = %backup.display(), "Unreadable cache file");
+
+        return Ok(None);
+    };
+
+    let from = legacy.version();
+    back_up(path, &format!("v{from}"))?;
+
+    let current = loop {
+        match legacy.upgrade() {
+            Upgrade::Legacy(next) => legacy = next,
+            Upgrade::Current(current) => break current,
+        }
+    };
+
+    write_cache(&path.with_extension("tmp"), path, version, current.clone())?;
+    tracing::info!(path = %path.display(), from, to = version, "Migrated cache file");
+
+    Ok(Some(current))
+}
+
+pub(super) fn back_up(path: &Path, label: &str) -> Result<PathBuf, CacherError> {
+    let backup = path.with_extension(format!("{label}.bak"));
+    fs::copy(path, &backup)?;
+
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::io::{LIBRARY_CACHE_VERSION, QUEUE_CACHE_VERSION, SCAN_RECORD_VERSION};
+    use super::super::legacy::{
+        CachedLibraryStateV5, CachedTrackV5, LegacyLibraryState, LegacyQueueState, LegacyScanRecord,
+    };
+    use super::super::schema::{
+        CachedLibraryState, CachedPlaylist, CachedPlaylistSource, CachedQueueState,
+        CachedSourceLocation, CachedTrackTags,
+    };
+    use super::*;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    // The ids the checked-in fixtures were captured with.
+    const TRACK_ID: [u8; 16] = [7; 16];
+    const PLAYLIST_ID: &str = "0b1f5a52-8c43-4d8e-9a39-64a2f3c1d2e7";
+
+    fn fixture_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wiremann-migrate-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Encodes `payload` with today's structs, as a cache file stamped `version`.
+    fn write_fixture<T: Encode>(dir: &Path, name: &str, version: u32, payload: T) -> PathBuf {
+        let path = dir.join(name);
+        write_cache(&dir.join("fixture.tmp"), &path, version, payload).unwrap();
+        path
+    }
+
+    fn playlist() -> (String, CachedPlaylist) {
+        let id = Uuid::new_v4().to_string();
+
+        let playlist = CachedPlaylist {
+            id: id.clone(),
+            name: "Fixture".to_string(),
+            tracks: vec![TRACK_ID],
+            ..CachedPlaylist::default()
+        };
+
+        (id, playlist)
+    }
+
+    #[test]
+    fn library_v1_migrates_through_every_version() {
+        let dir = fixture_dir();
+
+        let path = dir.join("library.bin");
+        fs::write(&path, include_bytes!("fixtures/library_v1.bin")).unwrap();
+
+        let library = read_migrated::<LegacyLibraryState>(&path, LIBRARY_CACHE_VERSION)
+            .unwrap()
+            .unwrap();
+
+        let track = &library.tracks[&TRACK_ID];
+        assert_eq!(track.title, "Song");
+        assert_eq!(track.artist, "Artist");
+        assert_eq!(track.duration, 180_000);
+        assert_eq!(
+            track.sources[0].location,
+            CachedSourceLocation::File("/music/song.flac".to_string())
+        );
+        assert_eq!(track.sources[0].size, 1024);
+        assert_eq!(track.sources[0].range, None);
+        assert_eq!(track.tags, CachedTrackTags::default());
+        assert!(track.chapters.is_empty());
+
+        let playlist = &library.playlists[PLAYLIST_ID];
+        assert_eq!(playlist.name, "Fixture");
+        assert_eq!(playlist.source, CachedPlaylistSource::User);
+        assert_eq!(playlist.tracks, vec![TRACK_ID]);
+
+        assert_eq!(
+            fs::read(dir.join("library.v1.bak")).unwrap(),
+            include_bytes!("fixtures/library_v1.bin")
+        );
+
+        let rewritten = read_cache::<CachedLibraryState>(&path, LIBRARY_CACHE_VERSION).unwrap();
+        assert_eq!(rewritten, Some(library));
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn library_v5_migrates_one_step() {
+        let dir = fixture_dir();
+        let (playlist_id, playlist) = playlist();
+
+        let track = CachedTrackV5 {
+            id: TRACK_ID,
+            title: "Song".to_string(),
+            ..CachedTrackV5::default()
+        };
+
+        let v5 = CachedLibraryStateV5 {
+            tracks: HashMap::from([(TRACK_ID, track)]),
+            playlists: HashMap::from([(playlist_id.clone(), playlist)]),
+        };
+
+        let path = write_fixture(&dir, "library.bin", 5, v5);
+
+        let library = read_migrated::<LegacyLibraryState>(&path, LIBRARY_CACHE_VERSION)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(library.tracks[&TRACK_ID].title, "Song");
+        assert!(library.playlists.contains_key(&playlist_id));
+        assert!(dir.join("library.v5.bak").exists());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn current_library_is_read_without_a_backup() {
+        let dir = fixture_dir();
+
+        let current = CachedLibraryState::default();
+        let path = write_fixture(&dir, "library.bin", LIBRARY_CACHE_VERSION, current.clone());
+
+        let library = read_migrated::<LegacyLibraryState>(&path, LIBRARY_CACHE_VERSION).unwrap();
+
+        assert_eq!(library, Some(current));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn unreadable_files_are_kept_aside() {
+        let dir = fixture_dir();
+        let path = dir.join("queue.bin");
+        fs::write(&path, b"not a cache file").unwrap();
+
+        let queue = read_migrated::<LegacyQueueState>(&path, QUEUE_CACHE_VERSION).unwrap();
+
+        assert_eq!(queue, None);
+        assert_eq!(
+            fs::read(dir.join("queue.unreadable.bak")).unwrap(),
+            b"not a cache file"
+        );
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn queue_v1_is_current() {
+        let dir = fixture_dir();
+
+        let v1 = CachedQueueState {
+            tracks: vec![TRACK_ID],
+            order: vec![0],
+        };
+
+        let path = write_fixture(&dir, "queue.bin", 1, v1.clone());
+
+        let queue = read_migrated::<LegacyQueueState>(&path, QUEUE_CACHE_VERSION).unwrap();
+
+        assert_eq!(queue, Some(v1));
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn scan_record_v1_keeps_files_that_still_match() {
+        let dir = fixture_dir();
+
+        // Recorded against `fixtures/track.bin`, relative to the crate root: once at its
+        // size, once at a size it no longer has, and once more for a file that is gone.
+        let path = dir.join("scan_record.bin");
+        fs::write(&path, include_bytes!("fixtures/scan_record_v1.bin")).unwrap();
+
+        let record = read_migrated::<LegacyScanRecord>(&path, SCAN_RECORD_VERSION)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(record.len(), 1);
+
+        let (kept, entry) = record.iter().next().unwrap();
+
+        assert_eq!(
+            kept.location,
+            CachedSourceLocation::File("src/cacher/fixtures/track.bin".to_string())
+        );
+        assert_eq!(entry.id, [1; 16]);
+        assert_eq!(kept.size, 4096);
+        assert_ne!(kept.modified, 42);
+        assert!(entry.content_hash.is_some());
+        assert!(dir.join("scan_record.v1.bak").exists());
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
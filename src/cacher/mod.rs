@@ -1,18 +1,27 @@
 pub mod images;
 pub mod io;
+pub mod legacy;
 pub mod lyrics;
+pub mod migrate;
 pub mod paths;
 pub mod schema;
+pub mod waveform;
 
-use crate::app::AppPaths;
+use crate::app::{AppPaths, spawn_pool_worker};
 use crate::controller::commands::CacherCommand;
 use crate::controller::events::CacherEvent;
-use crate::controller::state::{LibraryState, PlaybackState, QueueState};
+use crate::controller::state::{
+    AlarmState, BookmarkState, EqualizerState, LibraryState, PlaybackState, QueueState, ResumeState,
+};
 use crate::errors::CacherError;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, Sender, select};
+use std::sync::Arc;
 
 pub use io::CacheJob;
-pub use schema::{CachedImage, CachedTrackSource, ImageKind};
+pub use schema::{
+    CachedImage, CachedLoudnessFailures, CachedScanEntry, CachedScanRecord, CachedTrackSource,
+    ImageKind,
+};
 use tracing::error;
 
 #[derive(Clone)]
@@ -42,14 +51,20 @@ impl Cacher {
         let (thumb_tx, thumb_rx) = crossbeam_channel::unbounded();
         let (album_art_tx, album_art_rx) = crossbeam_channel::unbounded();
         let (playlist_thumbnail_tx, playlist_thumbnail_rx) = crossbeam_channel::unbounded();
+        let (crashed_tx, crashed_rx) = crossbeam_channel::unbounded();
 
-        self.spawn_app_state_worker(app_state_rx);
-        self.spawn_thumbnail_workers(&thumb_rx, workers);
-        self.spawn_album_art_worker(album_art_rx);
-        self.spawn_playlist_thumbnail_worker(playlist_thumbnail_rx);
+        self.spawn_app_state_worker(app_state_rx, &crashed_tx);
+        self.spawn_thumbnail_workers(&thumb_rx, workers, &crashed_tx);
+        self.spawn_album_art_worker(album_art_rx, &crashed_tx);
+        self.spawn_playlist_thumbnail_worker(playlist_thumbnail_rx, &crashed_tx);
 
         loop {
-            match self.rx.recv()? {
+            let command = select! {
+                recv(self.rx) -> command => command?,
+                recv(crashed_rx) -> message => return Err(CacherError::WorkerPanicked(message?)),
+            };
+
+            match command {
                 CacherCommand::WriteLibraryState(state) => {
                     let _ = app_state_tx.send(CacheJob::WriteLibraryState(state));
                 }
@@ -59,6 +74,18 @@ impl Cacher {
                 CacherCommand::WriteQueueState(state) => {
                     let _ = app_state_tx.send(CacheJob::WriteQueueState(state));
                 }
+                CacherCommand::WriteEqualizerState(state) => {
+                    let _ = app_state_tx.send(CacheJob::WriteEqualizerState(state));
+                }
+                CacherCommand::WriteBookmarks(state) => {
+                    let _ = app_state_tx.send(CacheJob::WriteBookmarks(state));
+                }
+                CacherCommand::WriteResumePositions(state) => {
+                    let _ = app_state_tx.send(CacheJob::WriteResumePositions(state));
+                }
+                CacherCommand::WriteAlarm(state) => {
+                    let _ = app_state_tx.send(CacheJob::WriteAlarm(state));
+                }
                 CacherCommand::WriteImage {
                     id,
                     kind,
@@ -138,6 +165,20 @@ impl Cacher {
                         error!(error = ?e, "Error occured while writing cached lyrics");
                     }
                 }
+                CacherCommand::GetWaveform(id) => {
+                    if let Ok(waveform) = self.read_cached_waveform(id) {
+                        self.tx
+                            .send(CacherEvent::Waveform(id, Arc::new(waveform)))
+                            .ok();
+                    } else {
+                        self.tx.send(CacherEvent::MissingWaveform(id)).ok();
+                    }
+                }
+                CacherCommand::WriteWaveform(id, waveform) => {
+                    if let Err(e) = self.write_cached_waveform(id, &waveform) {
+                        error!(error = ?e, "Error occured while writing cached waveform");
+                    }
+                }
             }
         }
     }
@@ -154,8 +195,24 @@ impl Cacher {
         io::write_queue_state_to_disk(&self.app_paths.cache, state)
     }
 
+    fn write_equalizer_state(&self, state: &EqualizerState) -> Result<(), CacherError> {
+        io::write_equalizer_state_to_disk(&self.app_paths.config, state)
+    }
+
+    fn write_bookmarks(&self, state: &BookmarkState) -> Result<(), CacherError> {
+        io::write_bookmarks_to_disk(&self.app_paths.cache, state)
+    }
+
+    fn write_resume_positions(&self, state: &ResumeState) -> Result<(), CacherError> {
+        io::write_resume_positions_to_disk(&self.app_paths.cache, state)
+    }
+
+    fn write_alarm(&self, state: &AlarmState) -> Result<(), CacherError> {
+        io::write_alarm_to_disk(&self.app_paths.config, state)
+    }
+
     fn load_app_state(&self) -> Result<crate::controller::state::AppState, CacherError> {
-        io::load_app_state(&self.app_paths.cache)
+        io::load_app_state(&self.app_paths.cache, &self.app_paths.config)
     }
 
     #[allow(dead_code)]
@@ -173,36 +230,46 @@ impl Cacher {
         io::read_playback_state_from_disk(&self.app_paths.cache)
     }
 
-    fn spawn_app_state_worker(&self, rx: Receiver<CacheJob>) {
+    fn spawn_app_state_worker(&self, rx: Receiver<CacheJob>, crashed: &Sender<String>) {
         let cacher = self.clone();
 
-        std::thread::spawn(move || {
-            loop {
-                while let Ok(job) = rx.recv() {
-                    let result: Result<(), CacherError> = (|| {
-                        match job {
-                            CacheJob::WriteLibraryState(state) => {
-                                cacher.write_library_state(&state)?;
-                            }
-                            CacheJob::WritePlaybackState(state) => {
-                                cacher.write_playback_state(&state)?;
-                            }
-                            CacheJob::WriteQueueState(state) => {
-                                cacher.write_queue_state(&state)?;
-                            }
-                            CacheJob::LoadAppState => {
-                                let state = cacher.load_app_state()?;
-                                let _ = cacher.tx.send(CacherEvent::AppState(state));
-                            }
-                            _ => {}
+        spawn_pool_worker("cacher app state", crashed, move || {
+            while let Ok(job) = rx.recv() {
+                let result: Result<(), CacherError> = (|| {
+                    match job {
+                        CacheJob::WriteLibraryState(state) => {
+                            cacher.write_library_state(&state)?;
+                        }
+                        CacheJob::WritePlaybackState(state) => {
+                            cacher.write_playback_state(&state)?;
+                        }
+                        CacheJob::WriteQueueState(state) => {
+                            cacher.write_queue_state(&state)?;
+                        }
+                        CacheJob::WriteEqualizerState(state) => {
+                            cacher.write_equalizer_state(&state)?;
                         }
+                        CacheJob::WriteBookmarks(state) => {
+                            cacher.write_bookmarks(&state)?;
+                        }
+                        CacheJob::WriteResumePositions(state) => {
+                            cacher.write_resume_positions(&state)?;
+                        }
+                        CacheJob::WriteAlarm(state) => {
+                            cacher.write_alarm(&state)?;
+                        }
+                        CacheJob::LoadAppState => {
+                            let state = cacher.load_app_state()?;
+                            let _ = cacher.tx.send(CacherEvent::AppState(state));
+                        }
+                        _ => {}
+                    }
 
-                        Ok(())
-                    })();
+                    Ok(())
+                })();
 
-                    if let Err(err) = result {
-                        error!(error = ?err, "Error occurred");
-                    }
+                if let Err(err) = result {
+                    error!(error = ?err, "Error occurred");
                 }
             }
         });
@@ -1,5 +1,9 @@
-use crate::controller::state::{ImageId, Track, TrackId, TrackSource};
-use crate::controller::state::{PlaybackState, PlaybackStatus, QueueState};
+use crate::controller::state::{Alarm, AlarmState, AudioProperties, TrackTags};
+use crate::controller::state::{
+    Bookmark, BookmarkState, Chapter, EqBand, EqPreset, EqualizerState, FadeCurve, PlaybackState,
+    PlaybackStatus, QueueState, ReplayGain, ReplayGainMode, ResumeOnLaunch, ResumeState, Waveform,
+};
+use crate::controller::state::{ImageId, SourceLocation, Track, TrackId, TrackRange, TrackSource};
 use crate::controller::state::{Playlist, PlaylistId, PlaylistSource};
 use bitcode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
@@ -7,7 +11,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, UNIX_EPOCH};
 use uuid::Uuid;
 
 use crate::controller::state::LibraryState;
@@ -37,14 +41,87 @@ pub struct CachedTrack {
 
     pub duration: u64,
 
+    pub tags: CachedTrackTags,
+    pub properties: CachedAudioProperties,
+
+    pub replay_gain: CachedReplayGain,
+
+    pub chapters: Vec<CachedChapter>,
+
     pub image_id: Option<[u8; 16]>,
 }
 
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedTrackTags {
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub year: Option<u32>,
+    pub date: Option<String>,
+    pub genre: Option<String>,
+    pub composer: Option<String>,
+    pub bpm: Option<f32>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedAudioProperties {
+    pub bitrate: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u8>,
+    pub codec: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedChapter {
+    pub title: String,
+    pub start: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedReplayGain {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Hash, Eq, Encode, Decode)]
 pub struct CachedTrackSource {
-    pub path: String,
+    pub location: CachedSourceLocation,
     pub size: u64,
     pub modified: u64,
+    pub range: Option<CachedTrackRange>,
+}
+
+// What the scan record knows about a source besides the source itself.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Encode, Decode)]
+pub struct CachedScanEntry {
+    pub id: [u8; 16],
+    pub content_hash: Option<[u8; 16]>,
+}
+
+pub type CachedScanRecord = HashMap<CachedTrackSource, CachedScanEntry>;
+
+// Sources loudness analysis couldn't decode.
+pub type CachedLoudnessFailures = Vec<CachedTrackSource>;
+
+#[derive(Debug, Clone, PartialEq, Hash, Eq, Encode, Decode)]
+pub enum CachedSourceLocation {
+    File(String),
+    Stream(String),
+}
+
+impl Default for CachedSourceLocation {
+    fn default() -> Self {
+        CachedSourceLocation::File(String::new())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Hash, Eq, Encode, Decode)]
+pub struct CachedTrackRange {
+    pub start: u64,
+    pub end: Option<u64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
@@ -83,6 +160,7 @@ pub struct CachedLibraryState {
 }
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct CachedPlaybackState {
     pub current: Option<[u8; 16]>,
     pub current_playlist: Option<String>,
@@ -90,11 +168,62 @@ pub struct CachedPlaybackState {
 
     pub status: PlaybackStatus,
     pub position: u64,
+    #[serde(default = "default_position_interval")]
+    pub position_interval: u64,
 
     pub volume: f32,
     pub mute: bool,
     pub shuffling: bool,
     pub repeat: bool,
+
+    #[serde(default)]
+    pub crossfade: u64,
+    #[serde(default)]
+    pub crossfade_curve: FadeCurve,
+
+    #[serde(default)]
+    pub replay_gain_mode: ReplayGainMode,
+
+    #[serde(default)]
+    pub resume_on_launch: ResumeOnLaunch,
+    #[serde(default = "default_resume_threshold")]
+    pub resume_threshold: u64,
+
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default)]
+    pub preserve_pitch: bool,
+
+    #[serde(default)]
+    pub skip_chapters: bool,
+
+    #[serde(default)]
+    pub output_device: Option<String>,
+}
+
+fn default_position_interval() -> u64 {
+    16
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_resume_threshold() -> u64 {
+    20 * 60 * 1000
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedEqualizerState {
+    pub enabled: bool,
+    pub bands: Vec<EqBand>,
+    pub preset: Option<String>,
+    pub presets: Vec<EqPreset>,
+
+    #[serde(default)]
+    pub track_presets: HashMap<[u8; 16], String>,
+    #[serde(default)]
+    pub album_presets: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
@@ -103,6 +232,39 @@ pub struct CachedQueueState {
     pub order: Vec<usize>,
 }
 
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct CachedWaveform {
+    pub peaks: Vec<(i8, i8)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct CachedBookmark {
+    pub name: String,
+    pub position: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedBookmarkState {
+    pub tracks: HashMap<[u8; 16], Vec<CachedBookmark>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
+pub struct CachedResumeState {
+    pub positions: HashMap<[u8; 16], u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CachedAlarmState {
+    pub alarm: Option<CachedAlarm>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedAlarm {
+    pub playlist: String,
+    // Seconds since the Unix epoch.
+    pub at: u64,
+}
+
 // Conversion implementations
 
 impl From<&Track> for CachedTrack {
@@ -114,6 +276,17 @@ impl From<&Track> for CachedTrack {
             artist: track.artist.clone(),
             album: track.album.clone(),
             duration: track.duration.as_millis() as u64,
+            tags: (&track.tags).into(),
+            properties: (&track.properties).into(),
+            replay_gain: (&track.replay_gain).into(),
+            chapters: track
+                .chapters
+                .iter()
+                .map(|chapter| CachedChapter {
+                    title: chapter.title.clone(),
+                    start: chapter.start.as_millis() as u64,
+                })
+                .collect(),
             image_id: track.image_id.map(|id| id.0),
         }
     }
@@ -128,17 +301,113 @@ impl From<CachedTrack> for Track {
             artist: c.artist,
             album: c.album,
             duration: Duration::from_millis(c.duration),
+            tags: c.tags.into(),
+            properties: c.properties.into(),
+            replay_gain: (&c.replay_gain).into(),
+            chapters: c
+                .chapters
+                .into_iter()
+                .map(|chapter| Chapter {
+                    title: chapter.title,
+                    start: Duration::from_millis(chapter.start),
+                })
+                .collect(),
             image_id: c.image_id.map(ImageId),
         }
     }
 }
 
+impl From<&TrackTags> for CachedTrackTags {
+    fn from(t: &TrackTags) -> Self {
+        CachedTrackTags {
+            album_artist: t.album_artist.clone(),
+            track_number: t.track_number,
+            disc_number: t.disc_number,
+            year: t.year,
+            date: t.date.clone(),
+            genre: t.genre.clone(),
+            composer: t.composer.clone(),
+            bpm: t.bpm,
+            comment: t.comment.clone(),
+        }
+    }
+}
+
+impl From<CachedTrackTags> for TrackTags {
+    fn from(c: CachedTrackTags) -> Self {
+        TrackTags {
+            album_artist: c.album_artist,
+            track_number: c.track_number,
+            disc_number: c.disc_number,
+            year: c.year,
+            date: c.date,
+            genre: c.genre,
+            composer: c.composer,
+            bpm: c.bpm,
+            comment: c.comment,
+        }
+    }
+}
+
+impl From<&AudioProperties> for CachedAudioProperties {
+    fn from(p: &AudioProperties) -> Self {
+        CachedAudioProperties {
+            bitrate: p.bitrate,
+            sample_rate: p.sample_rate,
+            channels: p.channels,
+            codec: p.codec.clone(),
+        }
+    }
+}
+
+impl From<CachedAudioProperties> for AudioProperties {
+    fn from(c: CachedAudioProperties) -> Self {
+        AudioProperties {
+            bitrate: c.bitrate,
+            sample_rate: c.sample_rate,
+            channels: c.channels,
+            codec: c.codec,
+        }
+    }
+}
+
+impl From<&ReplayGain> for CachedReplayGain {
+    fn from(r: &ReplayGain) -> Self {
+        CachedReplayGain {
+            track_gain: r.track_gain,
+            track_peak: r.track_peak,
+            album_gain: r.album_gain,
+            album_peak: r.album_peak,
+        }
+    }
+}
+
+impl From<&CachedReplayGain> for ReplayGain {
+    fn from(c: &CachedReplayGain) -> Self {
+        ReplayGain {
+            track_gain: c.track_gain,
+            track_peak: c.track_peak,
+            album_gain: c.album_gain,
+            album_peak: c.album_peak,
+        }
+    }
+}
+
 impl From<&TrackSource> for CachedTrackSource {
     fn from(c: &TrackSource) -> Self {
         CachedTrackSource {
-            path: c.path.to_string_lossy().to_string(),
+            location: match &c.location {
+                SourceLocation::File(path) => {
+                    CachedSourceLocation::File(path.to_string_lossy().to_string())
+                }
+                SourceLocation::Stream(url) => CachedSourceLocation::Stream(url.clone()),
+            },
             size: c.size,
             modified: c.modified,
+            range: c.range.map(|range| CachedTrackRange {
+                start: range.start.as_millis() as u64,
+                end: range.end.map(|end| end.as_millis() as u64),
+            }),
         }
     }
 }
@@ -146,9 +415,18 @@ impl From<&TrackSource> for CachedTrackSource {
 impl From<&CachedTrackSource> for TrackSource {
     fn from(c: &CachedTrackSource) -> Self {
         TrackSource {
-            path: PathBuf::from(c.path.clone()),
+            location: match &c.location {
+                CachedSourceLocation::File(path) => SourceLocation::File(PathBuf::from(path)),
+                CachedSourceLocation::Stream(url) => SourceLocation::Stream(url.clone()),
+            },
             size: c.size,
             modified: c.modified,
+            range: c.range.map(|range| TrackRange {
+                start: Duration::from_millis(range.start),
+                end: range.end.map(Duration::from_millis),
+            }),
+            content_hash: None,
+            unplayable: false,
         }
     }
 }
@@ -245,10 +523,20 @@ impl From<&PlaybackState> for CachedPlaybackState {
             current_index: p.current_index,
             status: p.status,
             position: p.position.as_millis() as u64,
+            position_interval: p.position_interval.as_millis() as u64,
             volume: p.volume,
             mute: p.mute,
             shuffling: p.shuffling,
             repeat: p.repeat,
+            crossfade: p.crossfade.as_millis() as u64,
+            crossfade_curve: p.crossfade_curve,
+            replay_gain_mode: p.replay_gain_mode,
+            resume_on_launch: p.resume_on_launch,
+            resume_threshold: p.resume_threshold.as_millis() as u64,
+            speed: p.speed,
+            preserve_pitch: p.preserve_pitch,
+            skip_chapters: p.skip_chapters,
+            output_device: p.output_device.clone(),
         }
     }
 }
@@ -263,10 +551,61 @@ impl From<CachedPlaybackState> for PlaybackState {
             current_index: c.current_index,
             status: c.status,
             position: Duration::from_millis(c.position),
+            position_interval: Duration::from_millis(c.position_interval),
             volume: c.volume,
             mute: c.mute,
             shuffling: c.shuffling,
             repeat: c.repeat,
+            crossfade: Duration::from_millis(c.crossfade),
+            crossfade_curve: c.crossfade_curve,
+            replay_gain_mode: c.replay_gain_mode,
+            resume_on_launch: c.resume_on_launch,
+            resume_threshold: Duration::from_millis(c.resume_threshold),
+            speed: c.speed,
+            preserve_pitch: c.preserve_pitch,
+            skip_chapters: c.skip_chapters,
+            loop_start: None,
+            loop_end: None,
+            waveform: None,
+            stream_title: None,
+            sleep_timer: None,
+            output_device: c.output_device,
+            active_output: None,
+            output_devices: Vec::new(),
+        }
+    }
+}
+
+impl From<&EqualizerState> for CachedEqualizerState {
+    fn from(e: &EqualizerState) -> Self {
+        Self {
+            enabled: e.enabled,
+            bands: e.bands.clone(),
+            preset: e.preset.clone(),
+            presets: e.presets.clone(),
+            track_presets: e
+                .track_presets
+                .iter()
+                .map(|(id, name)| (id.0, name.clone()))
+                .collect(),
+            album_presets: e.album_presets.clone(),
+        }
+    }
+}
+
+impl From<CachedEqualizerState> for EqualizerState {
+    fn from(c: CachedEqualizerState) -> Self {
+        Self {
+            enabled: c.enabled,
+            bands: c.bands,
+            preset: c.preset,
+            presets: c.presets,
+            track_presets: c
+                .track_presets
+                .into_iter()
+                .map(|(id, name)| (TrackId(id), name))
+                .collect(),
+            album_presets: c.album_presets,
         }
     }
 }
@@ -288,3 +627,126 @@ impl From<CachedQueueState> for QueueState {
         }
     }
 }
+
+impl From<&BookmarkState> for CachedBookmarkState {
+    fn from(b: &BookmarkState) -> Self {
+        Self {
+            tracks: b
+                .tracks
+                .iter()
+                .map(|(id, bookmarks)| {
+                    let bookmarks = bookmarks
+                        .iter()
+                        .map(|bookmark| CachedBookmark {
+                            name: bookmark.name.clone(),
+                            position: bookmark.position.as_millis() as u64,
+                        })
+                        .collect();
+
+                    (id.0, bookmarks)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<CachedBookmarkState> for BookmarkState {
+    fn from(c: CachedBookmarkState) -> Self {
+        Self {
+            tracks: c
+                .tracks
+                .into_iter()
+                .map(|(id, bookmarks)| {
+                    let bookmarks = bookmarks
+                        .into_iter()
+                        .map(|bookmark| Bookmark {
+                            name: bookmark.name,
+                            position: Duration::from_millis(bookmark.position),
+                        })
+                        .collect();
+
+                    (TrackId(id), bookmarks)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&ResumeState> for CachedResumeState {
+    fn from(r: &ResumeState) -> Self {
+        Self {
+            positions: r
+                .positions
+                .iter()
+                .map(|(id, position)| (id.0, position.as_millis() as u64))
+                .collect(),
+        }
+    }
+}
+
+impl From<CachedResumeState> for ResumeState {
+    fn from(c: CachedResumeState) -> Self {
+        Self {
+            positions: c
+                .positions
+                .into_iter()
+                .map(|(id, position)| (TrackId(id), Duration::from_millis(position)))
+                .collect(),
+        }
+    }
+}
+
+impl From<&AlarmState> for CachedAlarmState {
+    fn from(a: &AlarmState) -> Self {
+        Self {
+            alarm: a.alarm.map(|alarm| CachedAlarm {
+                playlist: alarm.playlist.0.to_string(),
+                at: alarm
+                    .at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            }),
+        }
+    }
+}
+
+impl From<CachedAlarmState> for AlarmState {
+    fn from(c: CachedAlarmState) -> Self {
+        Self {
+            alarm: c.alarm.and_then(|alarm| {
+                Some(Alarm {
+                    playlist: PlaylistId(Uuid::from_str(&alarm.playlist).ok()?),
+                    at: UNIX_EPOCH + Duration::from_secs(alarm.at),
+                })
+            }),
+            ramp: None,
+        }
+    }
+}
+
+impl From<&Waveform> for CachedWaveform {
+    fn from(w: &Waveform) -> Self {
+        let quantize = |v: f32| (v.clamp(-1.0, 1.0) * 127.0).round() as i8;
+
+        Self {
+            peaks: w
+                .peaks
+                .iter()
+                .map(|(min, max)| (quantize(*min), quantize(*max)))
+                .collect(),
+        }
+    }
+}
+
+impl From<CachedWaveform> for Waveform {
+    fn from(c: CachedWaveform) -> Self {
+        Self {
+            peaks: c
+                .peaks
+                .into_iter()
+                .map(|(min, max)| (f32::from(min) / 127.0, f32::from(max) / 127.0))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{
+    cacher::{Cacher, schema::CachedWaveform},
+    controller::state::{TrackId, Waveform},
+    errors::CacherError,
+};
+use std::{
+    fs,
+    io::{Cursor, Write},
+    path::PathBuf,
+};
+
+impl Cacher {
+    fn cached_waveform_path(&self, id: TrackId) -> PathBuf {
+        let hex = hex::encode(id.0);
+        let folder = &hex[0..2];
+
+        self.app_paths
+            .cache
+            .join("waveforms")
+            .join(folder)
+            .join(format!("{hex}.peaks.zstd"))
+    }
+
+    pub(super) fn write_cached_waveform(
+        &self,
+        id: TrackId,
+        waveform: &Waveform,
+    ) -> Result<(), CacherError> {
+        let final_path = self.cached_waveform_path(id);
+        let tmp_path = final_path.with_extension("tmp");
+
+        if final_path.exists() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(final_path.parent().unwrap())?;
+
+        let bytes = bitcode::encode(&CachedWaveform::from(waveform));
+
+        let compressed = zstd::encode_all(Cursor::new(bytes), 4)?;
+
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&compressed)?;
+            file.sync_all()?;
+        }
+
+        fs::rename(tmp_path, final_path)?;
+
+        Ok(())
+    }
+
+    pub(super) fn read_cached_waveform(&self, id: TrackId) -> Result<Waveform, CacherError> {
+        let path = self.cached_waveform_path(id);
+
+        let bytes = fs::read(path)?;
+
+        let decompressed = zstd::decode_all(Cursor::new(bytes))?;
+
+        let cached_waveform: CachedWaveform = bitcode::decode(&decompressed)?;
+
+        Ok(cached_waveform.into())
+    }
+}
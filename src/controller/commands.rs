@@ -1,27 +1,53 @@
 use crate::cacher::ImageKind;
 use crate::controller::state::PlaylistId;
-use crate::controller::state::{ImageId, TrackId};
-use crate::controller::state::{LibraryState, PlaybackState, PlaybackStatus, QueueState};
+use crate::controller::state::{
+    AlarmState, BookmarkState, EqBand, EqualizerState, FadeCurve, LibraryState, PlaybackState,
+    PlaybackStatus, QueueState, ReplayGain, ReplayGainMode, ResumeState,
+};
+use crate::controller::state::{ImageId, TrackId, TrackSource, Waveform};
 use crate::lyrics_manager::Lyrics;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 pub enum AudioCommand {
-    Load(TrackId, PathBuf),
-    GetPosition,
-    CheckTrackEnded,
+    Load(TrackId, TrackSource, ReplayGain),
+    // Loads paused at the given position, without any audible start.
+    Cue(TrackId, TrackSource, ReplayGain, Duration),
+    Preload(TrackId, TrackSource, ReplayGain),
+    // Drops the preloaded track, for when the queue no longer leads to it.
+    ClearPreload,
+    SetPositionInterval(Duration),
     Play,
     Pause,
     Stop,
     SetVolume(f32),
+    SetCrossfade(Duration, FadeCurve),
+    SetReplayGainMode(ReplayGainMode),
+    UpdateReplayGain(TrackId, ReplayGain),
+    SetEq(Vec<EqBand>),
+    SetEqEnabled(bool),
+    SetSpeed(f32),
+    SetPreservePitch(bool),
+    SetLoop { start: Duration, end: Duration },
+    ClearLoop,
+    SetAnalysis(bool),
+    ListDevices,
+    SetDevice(Option<String>),
     Seek(Duration),
 }
 
 pub enum ScannerCommand {
     ScanDir(PathBuf),
     ScanTrack(PathBuf),
+    // Keeps the library in step with the folder a playlist was scanned from.
+    WatchFolder(PlaylistId, PathBuf),
+    // Walks these folder roots again to pick up what changed and drop what's gone.
+    Rescan(Vec<(PlaylistId, PathBuf)>),
     StartNextScan,
+    AnalyzeLoudness(TrackId, TrackSource),
+    AnalyzeWaveform(TrackId, TrackSource),
 }
 
 pub enum ImageProcessorCommand {
@@ -40,6 +66,10 @@ pub enum CacherCommand {
     WriteLibraryState(LibraryState),
     WritePlaybackState(PlaybackState),
     WriteQueueState(QueueState),
+    WriteEqualizerState(EqualizerState),
+    WriteBookmarks(BookmarkState),
+    WriteResumePositions(ResumeState),
+    WriteAlarm(AlarmState),
 
     GetImage(HashSet<ImageId>, ImageKind),
     WriteImage {
@@ -52,6 +82,9 @@ pub enum CacherCommand {
 
     GetLyrics(TrackId),
     WriteLyrics(TrackId, Lyrics),
+
+    GetWaveform(TrackId),
+    WriteWaveform(TrackId, Arc<Waveform>),
 }
 
 pub enum SystemIntegrationCommand {
@@ -1,6 +1,7 @@
+use crate::audio::analysis::Analysis;
 use crate::cacher::ImageKind;
-use crate::controller::state::{AppState, PlaybackStatus};
-use crate::controller::state::{ImageId, Track, TrackId, TrackSource};
+use crate::controller::state::{AppState, Engine, EngineHealth, PlaybackStatus, SourceLocation};
+use crate::controller::state::{ImageId, ReplayGain, Track, TrackId, TrackSource, Waveform};
 use crate::controller::state::{Playlist, PlaylistId};
 use crate::lyrics_manager::Lyrics;
 use gpui::RenderImage;
@@ -11,10 +12,44 @@ use std::time::Duration;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum AudioEvent {
-    TrackLoaded(TrackId, PathBuf),
+    TrackLoaded(TrackId, SourceLocation),
+    TrackTransitioned(TrackId, SourceLocation),
+    // A radio stream announced a new title through its ICY metadata.
+    StreamTitle(TrackId, String),
     Position(Duration),
+    // The engine dropped the A-B loop on its own, because the track changed under it or it
+    // couldn't seek back.
+    LoopCleared,
     PlaybackStatus(PlaybackStatus),
     TrackEnded,
+    DevicesChanged {
+        devices: Vec<String>,
+        current: Option<String>,
+    },
+    Analysis(Analysis),
+    Error {
+        track: Option<TrackId>,
+        // The source of the track that failed, as played.
+        location: Option<SourceLocation>,
+        kind: AudioErrorKind,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AudioErrorKind {
+    Missing,
+    Unsupported(String),
+    Decode(String),
+    Seek(String),
+    Io(String),
+}
+
+impl AudioErrorKind {
+    // Seek failures leave the track playing; everything else means it can't be played at all.
+    #[must_use]
+    pub fn is_unplayable(&self) -> bool {
+        !matches!(self, AudioErrorKind::Seek(_))
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -24,6 +59,8 @@ pub enum ScannerEvent {
 
     AddTrackSource(TrackId, TrackSource),
     RemoveTrackSource(TrackId, PathBuf),
+    // Tracks that lost their last source to a change on disk.
+    RemoveTracks(Vec<TrackId>),
 
     InsertPlaylist(Playlist),
 
@@ -31,6 +68,20 @@ pub enum ScannerEvent {
     Discovered(usize),
     Processed { processed: usize, total: usize },
     ScanFinished,
+
+    RescanFinished(RescanSummary),
+
+    LoudnessAnalyzed(TrackId, ReplayGain),
+    WaveformAnalyzed(TrackId, Arc<Waveform>),
+}
+
+/// What a rescan of the folder playlists changed in the library.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RescanSummary {
+    pub added: usize,
+    pub updated: usize,
+    // Tracks that lost their last source, to be dropped from the library.
+    pub removed: Vec<TrackId>,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -50,12 +101,14 @@ pub enum CacherEvent {
     PlaylistThumbnail(ImageId, Arc<RenderImage>),
 
     Lyrics(TrackId, Option<Lyrics>),
+    Waveform(TrackId, Arc<Waveform>),
 
     MissingThumbnails(Vec<ImageId>),
     MissingAlbumArt(ImageId),
     MissingPlaylistThumbnail(ImageId),
 
     MissingLyrics(TrackId),
+    MissingWaveform(TrackId),
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -76,3 +129,9 @@ pub enum SystemIntegrationEvent {
 pub enum LyricsEvent {
     Lyrics(TrackId, Option<Lyrics>),
 }
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum SupervisorEvent {
+    Health(Engine, EngineHealth),
+    Restarted(Engine),
+}
@@ -1,4 +1,4 @@
-use super::{Controller, App, AudioEvent, Entity, Wiremann, ControllerError, Duration, duration_to_slider, SystemIntegrationCommand, CacherCommand, ScannerCommand, HashSet, ImageKind, ImageProcessorCommand, LyricsState, LyricsStatus};
+use super::{Controller, App, AudioEvent, Entity, Wiremann, ControllerError, Duration, duration_to_slider, SystemIntegrationCommand, CacherCommand, ScannerCommand, HashSet, ImageKind, ImageProcessorCommand, LyricsState, LyricsStatus, ReplayGainMode, VisualizerState, AudioErrorKind, SourceLocation};
 
 impl Controller {
     pub fn handle_audio_event(
@@ -48,11 +48,22 @@ impl Controller {
                     let _ = self
                         .cacher_tx
                         .send(CacherCommand::WritePlaybackState(state));
+
+                    self.record_resume_position(*pos, cx);
+                    self.tick_sleep_timer(cx);
                 }
             }
-            AudioEvent::TrackLoaded(track_id, path) => {
+            AudioEvent::TrackLoaded(track_id, location)
+            | AudioEvent::TrackTransitioned(track_id, location) => {
                 let state = self.state.read(cx);
-                if !state.library.tracks.contains_key(track_id) {
+                let path = match location {
+                    SourceLocation::File(path) => Some(path),
+                    SourceLocation::Stream(_) => None,
+                };
+
+                if !state.library.tracks.contains_key(track_id)
+                    && let Some(path) = path
+                {
                     let _ = self
                         .scanner_tx
                         .send(ScannerCommand::ScanTrack(path.clone()));
@@ -64,30 +75,56 @@ impl Controller {
                             HashSet::from([image_id]),
                             ImageKind::AlbumArt,
                         ));
-                    } else {
+                    } else if let Some(path) = path {
                         let _ = self.image_processor_tx.send(
                             ImageProcessorCommand::GetCurrentAlbumArt(*track_id, path.clone()),
                         );
                     }
 
+                    let (title, artist) = match &state.playback.stream_title {
+                        Some((id, title)) if id == track_id => (title.clone(), track.title.clone()),
+                        _ => (track.title.clone(), track.artist.clone()),
+                    };
+
                     self.system_integration_tx
                         .send(SystemIntegrationCommand::SetMetadata {
-                            title: track.title.clone(),
-                            artist: track.artist.clone(),
+                            title,
+                            artist,
                             album: track.album.clone(),
                             image: None,
                             duration: track.duration.as_secs(),
                         })
                         .ok();
 
-                    self.cacher_tx
-                        .send(CacherCommand::GetLyrics(*track_id))
-                        .ok();
+                    // Radio has no lyrics or waveform, and can't be measured for loudness.
+                    if path.is_some() {
+                        self.cacher_tx
+                            .send(CacherCommand::GetLyrics(*track_id))
+                            .ok();
+
+                        self.cacher_tx
+                            .send(CacherCommand::GetWaveform(*track_id))
+                            .ok();
+                    }
+
+                    if path.is_some()
+                        && track.replay_gain.is_empty()
+                        && state.playback.replay_gain_mode != ReplayGainMode::Off
+                        && let Some(source) = track.get_valid_source()
+                    {
+                        self.scanner_tx
+                            .send(ScannerCommand::AnalyzeLoudness(*track_id, source.clone()))
+                            .ok();
+                    }
 
                     let lyrics_state = cx.global::<LyricsState>().0.clone();
 
                     lyrics_state.update(cx, |this, cx| {
-                        this.status = LyricsStatus::Fetching;
+                        this.status = if path.is_some() {
+                            LyricsStatus::Fetching
+                        } else {
+                            LyricsStatus::Unavailable
+                        };
                         this.lyrics = None;
                         this.track_id = Some(*track_id);
 
@@ -95,7 +132,19 @@ impl Controller {
                     });
                 }
                 self.state.update(cx, |this, cx| {
+                    if this.playback.current != Some(*track_id) {
+                        this.playback.waveform = None;
+                    }
+
                     this.playback.current = Some(*track_id);
+                    if this
+                        .playback
+                        .stream_title
+                        .as_ref()
+                        .is_some_and(|(id, _)| id != track_id)
+                    {
+                        this.playback.stream_title = None;
+                    }
 
                     if let Some(idx) = this.queue.get_index(*track_id) {
                         this.playback.current_index = idx;
@@ -108,6 +157,32 @@ impl Controller {
                 let _ = self
                     .cacher_tx
                     .send(CacherCommand::WritePlaybackState(state));
+
+                self.clear_ab_loop(cx);
+                self.apply_eq(cx);
+                self.preload_queue_next(cx);
+            }
+            AudioEvent::StreamTitle(track_id, title) => {
+                let state = self.state.read(cx);
+
+                if state.playback.current == Some(*track_id)
+                    && let Some(track) = state.library.tracks.get(track_id)
+                {
+                    self.system_integration_tx
+                        .send(SystemIntegrationCommand::SetMetadata {
+                            title: title.clone(),
+                            artist: track.title.clone(),
+                            album: track.album.clone(),
+                            image: None,
+                            duration: 0,
+                        })
+                        .ok();
+                }
+
+                self.state.update(cx, |this, cx| {
+                    this.playback.stream_title = Some((*track_id, title.clone()));
+                    cx.notify();
+                });
             }
             AudioEvent::PlaybackStatus(status) => {
                 self.state.update(cx, |this, cx| {
@@ -126,13 +201,79 @@ impl Controller {
                     .cacher_tx
                     .send(CacherCommand::WritePlaybackState(state));
             }
+            AudioEvent::LoopCleared => {
+                self.state.update(cx, |this, cx| {
+                    this.playback.loop_start = None;
+                    this.playback.loop_end = None;
+                    cx.notify();
+                });
+            }
+            AudioEvent::DevicesChanged { devices, current } => {
+                self.state.update(cx, |this, cx| {
+                    this.playback.output_devices.clone_from(devices);
+                    this.playback.active_output.clone_from(current);
+                    cx.notify();
+                });
+            }
+            AudioEvent::Analysis(analysis) => {
+                let visualizer_state = cx.global::<VisualizerState>().0.clone();
+
+                visualizer_state.update(cx, |this, cx| {
+                    this.apply(analysis);
+                    cx.notify();
+                });
+            }
+            AudioEvent::Error {
+                track,
+                location,
+                kind,
+            } => {
+                let state = self.state.read(cx);
+
+                let title = track
+                    .and_then(|id| state.library.tracks.get(&id))
+                    .map_or_else(|| "track".to_string(), |track| track.title.clone());
+
+                let message = match kind {
+                    AudioErrorKind::Missing => format!("Couldn't find {title} on disk"),
+                    AudioErrorKind::Unsupported(e) => format!("Can't play {title}: {e}"),
+                    AudioErrorKind::Decode(e) | AudioErrorKind::Io(e) => {
+                        format!("Couldn't play {title}: {e}")
+                    }
+                    AudioErrorKind::Seek(e) => format!("Couldn't seek in {title}: {e}"),
+                };
+
+                let skip = kind.is_unplayable()
+                    && track.is_some()
+                    && state.queue.get_id(state.playback.current_index) == *track;
+
+                view.update(cx, |this, cx| {
+                    this.toast_manager.update(cx, |this, cx| {
+                        this.error(message, cx);
+                    });
+                });
+
+                if let Some(id) = track
+                    && let Some(location) = location
+                    && kind.is_unplayable()
+                {
+                    self.mark_unplayable(*id, location, cx);
+                }
+
+                if skip {
+                    self.next_track(cx);
+                }
+            }
             AudioEvent::TrackEnded => {
                 let repeat = self.state.read(cx).playback.repeat;
 
-                if repeat {
+                if self.sleep_ends_with_track(cx) {
+                    self.stop();
+                    self.set_sleep_timer(None, cx);
+                } else if repeat {
                     self.load_queue_current(cx);
                 } else {
-                    self.next(cx);
+                    self.next_track(cx);
                 }
             }
         }
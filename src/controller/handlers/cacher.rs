@@ -1,4 +1,4 @@
-use super::{Controller, App, CacherEvent, Entity, Wiremann, ControllerError, PlaybackStatus, duration_to_slider, ImageCache, drop_image_from_app, Rgb, Rgba, rgb, SystemIntegrationCommand, DominantColors, ImageProcessorCommand, HashSet, ImageKind, pick_playlist_thumbnail_tracks, LyricsState, LyricsStatus};
+use super::{Controller, App, CacherEvent, Entity, Wiremann, ControllerError, duration_to_slider, ImageCache, drop_image_from_app, Rgb, Rgba, rgb, SystemIntegrationCommand, DominantColors, ImageProcessorCommand, HashSet, ImageKind, pick_playlist_thumbnail_tracks, LyricsState, LyricsStatus, ScannerCommand, TrackSource};
 
 impl Controller {
     pub fn handle_cacher_event(
@@ -9,20 +9,14 @@ impl Controller {
     ) -> Result<(), ControllerError> {
         match event {
             CacherEvent::AppState(state) => {
-                let playback_state = state.playback.clone();
                 self.state.update(cx, |this, _| {
                     *this = state.clone();
+                    this.playback.apply_resume_on_launch();
                 });
+                let playback_state = self.state.read(cx).playback.clone();
 
-                self.load_queue_current(cx);
-                self.set_volume(playback_state.volume, cx);
-                self.seek(playback_state.position);
-
-                match playback_state.status {
-                    PlaybackStatus::Stopped => self.stop(),
-                    PlaybackStatus::Paused => self.pause(),
-                    PlaybackStatus::Playing => self.play(),
-                }
+                self.restore_audio(cx);
+                self.watch_folders(cx);
 
                 let duration = if let Some(current) = playback_state.current
                     && let Some(track) = state.library.tracks.get(&current)
@@ -34,6 +28,7 @@ impl Controller {
 
                 view.update(cx, |this, cx| {
                     this.player_page.update(cx, |this, cx| {
+                        this.equalizer.update(cx, |this, cx| this.sync(cx));
                         this.controlbar.update(cx, |this, cx| {
                             this.vol_slider_state.update(cx, |this, cx| {
                                 this.set_value(playback_state.volume * 100.0, cx);
@@ -150,13 +145,13 @@ impl Controller {
 
                 if let Some(track_id) = track_id
                     && let Some(track) = tracks.get(track_id)
-                    && let Some(source) = track.get_valid_source()
+                    && let Some(path) = track.get_valid_source().and_then(TrackSource::path)
                 {
                     let _ =
                         self.image_processor_tx
                             .send(ImageProcessorCommand::GetCurrentAlbumArt(
                                 *track_id,
-                                source.path.clone(),
+                                path.to_path_buf(),
                             ));
                 }
             }
@@ -181,10 +176,10 @@ impl Controller {
 
                     if let Some(track_id) = track_id
                         && let Some(track) = tracks.get(track_id)
-                        && let Some(source) = track.get_valid_source()
+                        && let Some(path) = track.get_valid_source().and_then(TrackSource::path)
                     {
                         let mut set = HashSet::new();
-                        set.insert((*track_id, source.path.clone()));
+                        set.insert((*track_id, path.to_path_buf()));
                         let _ = self
                             .image_processor_tx
                             .send(ImageProcessorCommand::GetThumbnails(
@@ -246,6 +241,17 @@ impl Controller {
                     });
                 }
             }
+            CacherEvent::Waveform(id, waveform) => self.set_waveform(*id, waveform, cx),
+            CacherEvent::MissingWaveform(id) => {
+                if let Some(track) = self.state.read(cx).library.tracks.get(id)
+                    && let Some(source) = track.get_valid_source()
+                    && !source.is_stream()
+                {
+                    self.scanner_tx
+                        .send(ScannerCommand::AnalyzeWaveform(*id, source.clone()))
+                        .ok();
+                }
+            }
             CacherEvent::MissingLyrics(id) => {
                 if let Some(track) = self.state.read(cx).library.tracks.get(id) {
                     self.get_lyrics(
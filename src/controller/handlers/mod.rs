@@ -3,6 +3,7 @@ pub mod cacher;
 pub mod image_processor;
 pub mod lyrics;
 pub mod scanner;
+pub mod supervisor;
 pub mod system_integration;
 
-use super::{Controller, App, AudioEvent, Entity, Wiremann, ControllerError, Duration, duration_to_slider, SystemIntegrationCommand, CacherCommand, ScannerCommand, HashSet, ImageKind, ImageProcessorCommand, LyricsState, LyricsStatus, CacherEvent, PlaybackStatus, ImageCache, drop_image_from_app, Rgb, Rgba, rgb, DominantColors, pick_playlist_thumbnail_tracks, ImageProcessorEvent, Arc, LyricsEvent, ScannerEvent, ScanningStatus, TrackId, PathBuf, ToastKind, ToastPhase, Instant, PlaylistId, SystemIntegrationEvent};
+use super::{Controller, App, AudioEvent, Entity, Wiremann, ControllerError, Duration, duration_to_slider, SystemIntegrationCommand, CacherCommand, ScannerCommand, HashSet, ImageKind, ImageProcessorCommand, LyricsState, LyricsStatus, CacherEvent, PlaybackStatus, ImageCache, drop_image_from_app, Rgb, Rgba, rgb, DominantColors, pick_playlist_thumbnail_tracks, ImageProcessorEvent, Arc, LyricsEvent, ScannerEvent, ScanningStatus, TrackId, PathBuf, ToastKind, ToastPhase, Instant, PlaylistId, SystemIntegrationEvent, AudioCommand, ReplayGainMode, VisualizerState, AudioErrorKind, Engine, EngineHealth, EngineHealthState, SupervisorEvent, SourceLocation, TrackSource};
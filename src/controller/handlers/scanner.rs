@@ -1,4 +1,4 @@
-use super::{Controller, App, ScannerEvent, Entity, Wiremann, ControllerError, HashSet, Arc, CacherCommand, ScanningStatus, ScannerCommand, TrackId, PathBuf, ImageProcessorCommand, ImageKind, ToastKind, ToastPhase, Instant, PlaylistId};
+use super::{Controller, App, ScannerEvent, Entity, Wiremann, ControllerError, HashSet, Arc, CacherCommand, ScanningStatus, ScannerCommand, TrackId, PathBuf, ImageProcessorCommand, ImageKind, ToastKind, ToastPhase, Instant, PlaylistId, AudioCommand, TrackSource};
 
 impl Controller {
     pub fn handle_scanner_event(
@@ -18,22 +18,53 @@ impl Controller {
                         if let Some(existing) = this.library.tracks.get_mut(&id) {
                             let existing = Arc::make_mut(existing);
 
+                            let mut changed = false;
+
                             for src in &track.sources {
-                                if !existing.sources.iter().any(|s| s.path == src.path) {
-                                    existing.sources.push(src.clone());
+                                match existing
+                                    .sources
+                                    .iter_mut()
+                                    .find(|s| s.location == src.location)
+                                {
+                                    Some(old)
+                                        if old.size != src.size
+                                            || old.modified != src.modified
+                                            || old.content_hash != src.content_hash =>
+                                    {
+                                        *old = src.clone();
+                                        changed = true;
+                                    }
+                                    Some(_) => {}
+                                    None => existing.sources.push(src.clone()),
                                 }
                             }
 
-                            if existing.title.is_empty() && !track.title.is_empty() {
+                            // The file was rewritten since we last read it, so everything that
+                            // came from it is replaced, not just the fields we had nothing for.
+                            if changed {
                                 existing.title.clone_from(&track.title);
-                            }
-
-                            if existing.artist.is_empty() && !track.artist.is_empty() {
                                 existing.artist.clone_from(&track.artist);
-                            }
-
-                            if existing.album.is_empty() && !track.album.is_empty() {
                                 existing.album.clone_from(&track.album);
+                                existing.duration = track.duration;
+                                existing.replay_gain = track.replay_gain;
+                                existing.tags.clone_from(&track.tags);
+                                existing.properties.clone_from(&track.properties);
+                            } else {
+                                if existing.title.is_empty() && !track.title.is_empty() {
+                                    existing.title.clone_from(&track.title);
+                                }
+
+                                if existing.artist.is_empty() && !track.artist.is_empty() {
+                                    existing.artist.clone_from(&track.artist);
+                                }
+
+                                if existing.album.is_empty() && !track.album.is_empty() {
+                                    existing.album.clone_from(&track.album);
+                                }
+
+                                if existing.replay_gain.is_empty() {
+                                    existing.replay_gain = track.replay_gain;
+                                }
                             }
                         } else {
                             this.library.tracks.insert(id, Arc::new(track.clone()));
@@ -81,8 +112,10 @@ impl Controller {
             ScannerEvent::RemoveTrackSource(id, path) => {
                 self.state.update(cx, |this, cx| {
                     if let Some(track) = this.library.tracks.get_mut(id)
-                        && let Some(source) =
-                            track.sources.iter().position(|this| this.path == *path)
+                        && let Some(source) = track
+                            .sources
+                            .iter()
+                            .position(|this| this.path() == Some(path.as_path()))
                     {
                         Arc::make_mut(track).sources.remove(source);
                     }
@@ -92,6 +125,45 @@ impl Controller {
                 let state = self.state.read(cx).library.clone();
                 let _ = self.cacher_tx.send(CacherCommand::WriteLibraryState(state));
             }
+            ScannerEvent::RemoveTracks(ids) => self.remove_lost_tracks(ids, cx),
+            ScannerEvent::RescanFinished(summary) => {
+                self.remove_lost_tracks(&summary.removed, cx);
+
+                let message = format!(
+                    "Rescan complete: {} added, {} updated, {} removed",
+                    summary.added,
+                    summary.updated,
+                    summary.removed.len()
+                );
+
+                view.update(cx, |this, cx| {
+                    this.toast_manager
+                        .update(cx, |this, cx| this.success(message, cx));
+                });
+            }
+            ScannerEvent::LoudnessAnalyzed(id, replay_gain) => {
+                self.state.update(cx, |this, cx| {
+                    if let Some(track) = this.library.tracks.get_mut(id) {
+                        Arc::make_mut(track).replay_gain = *replay_gain;
+                    }
+
+                    cx.notify();
+                });
+
+                self.audio_tx
+                    .send(AudioCommand::UpdateReplayGain(*id, *replay_gain))
+                    .ok();
+
+                let state = self.state.read(cx).library.clone();
+                let _ = self.cacher_tx.send(CacherCommand::WriteLibraryState(state));
+            }
+            ScannerEvent::WaveformAnalyzed(id, waveform) => {
+                self.set_waveform(*id, waveform, cx);
+
+                self.cacher_tx
+                    .send(CacherCommand::WriteWaveform(*id, waveform.clone()))
+                    .ok();
+            }
             ScannerEvent::InsertPlaylist(playlist) => {
                 self.state.update(cx, |this, cx| {
                     this.library.playlists.insert(playlist.id, playlist.clone());
@@ -159,8 +231,8 @@ impl Controller {
                     .filter_map(|(id, track)| {
                         track
                             .get_valid_source()
-                            .map(|src| src.path.clone())
-                            .map(|path| (*id, path))
+                            .and_then(TrackSource::path)
+                            .map(|path| (*id, path.to_path_buf()))
                     })
                     .collect();
                 let _ = self
@@ -0,0 +1,66 @@
+use super::{App, Controller, ControllerError, Engine, EngineHealth, EngineHealthState, Entity, Instant, SupervisorEvent, ToastKind, ToastPhase, Wiremann};
+
+impl Controller {
+    pub fn handle_supervisor_event(
+        &mut self,
+        cx: &mut App,
+        event: &SupervisorEvent,
+        view: &Entity<Wiremann>,
+    ) -> Result<(), ControllerError> {
+        match event {
+            SupervisorEvent::Health(engine, health) => {
+                let engine_health = cx.global::<EngineHealthState>().0.clone();
+
+                engine_health.update(cx, |this, cx| {
+                    this.engines.insert(*engine, health.clone());
+                    cx.notify();
+                });
+
+                let message = match health {
+                    EngineHealth::Restarting { .. } => Some(format!(
+                        "The {} engine crashed, restarting...",
+                        engine.name()
+                    )),
+                    EngineHealth::Failed(_) => Some(format!(
+                        "The {} engine keeps crashing and was stopped",
+                        engine.name()
+                    )),
+                    EngineHealth::Running | EngineHealth::Stopped => None,
+                };
+
+                if let Some(message) = message {
+                    view.update(cx, |this, cx| {
+                        this.toast_manager.update(cx, |this, cx| {
+                            this.error(message, cx);
+                        });
+                    });
+                }
+            }
+            SupervisorEvent::Restarted(engine) => {
+                view.update(cx, |this, cx| {
+                    this.toast_manager.update(cx, |this, cx| {
+                        // A scan in progress was lost with the old scanner.
+                        if *engine == Engine::Scanner {
+                            this.toasts.update(cx, |list, _| {
+                                for t in list.iter_mut() {
+                                    if matches!(t.kind, ToastKind::ScanProgress(_))
+                                        && t.phase != ToastPhase::Exiting
+                                    {
+                                        t.phase = ToastPhase::Exiting;
+                                        t.exiting_at = Some(Instant::now());
+                                    }
+                                }
+                            });
+                        }
+
+                        this.success(format!("The {} engine recovered", engine.name()), cx);
+                    });
+                });
+
+                self.recover_engine(*engine, cx);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -7,14 +7,20 @@ use crate::controller::commands::{
     CacherCommand, ImageProcessorCommand, LyricsCommand, SystemIntegrationCommand,
 };
 use crate::controller::events::{
-    CacherEvent, ImageProcessorEvent, LyricsEvent, SystemIntegrationEvent,
+    CacherEvent, ImageProcessorEvent, LyricsEvent, SupervisorEvent, SystemIntegrationEvent,
 };
-use crate::controller::state::PlaybackStatus;
-use crate::controller::state::PlaylistId;
-use crate::controller::state::{Track, TrackId};
+use crate::controller::state::{Alarm, AlarmRamp, SleepTimer};
+use crate::controller::state::{
+    Bookmark, EqPreset, SourceLocation, Track, TrackId, TrackSource, Waveform,
+};
+use crate::controller::state::{Engine, EngineHealth};
+use crate::controller::state::{FadeCurve, PlaybackStatus, ReplayGainMode, ResumeOnLaunch};
+use crate::controller::state::{PlaylistId, PlaylistSource};
+use crate::ui::components::engine_health::EngineHealthState;
 use crate::ui::components::lyrics::{LyricsState, LyricsStatus};
 use crate::ui::components::toasts::scanning_status::ScanningStatus;
 use crate::ui::components::toasts::{ToastKind, ToastPhase};
+use crate::ui::components::visualizer::VisualizerState;
 use crate::ui::helpers::{drop_image_from_app, duration_to_slider};
 use crate::ui::theme::DominantColors;
 use crate::ui::wiremann::Wiremann;
@@ -23,15 +29,34 @@ use crate::{
 };
 use commands::{AudioCommand, ScannerCommand};
 use crossbeam_channel::{Receiver, Sender};
-use events::{AudioEvent, ScannerEvent};
+use events::{AudioErrorKind, AudioEvent, ScannerEvent};
 use gpui::{App, Entity, Global, Rgba, rgb};
 use okmain::rgb::Rgb;
 use rand::rng;
 use rand::seq::{IteratorRandom, SliceRandom};
 use std::collections::{HashMap, HashSet};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use std::{path::PathBuf, sync::Arc};
 
+// Resume positions are only rewritten once playback has moved this far from the saved one.
+const RESUME_SAVE_STEP: Duration = Duration::from_secs(5);
+// A track this close to its end counts as finished and starts over next time.
+const RESUME_END_MARGIN: Duration = Duration::from_secs(30);
+// Previous restarts the current chapter unless it began less than this long ago.
+const CHAPTER_RESTART_AFTER: Duration = Duration::from_secs(3);
+// The sleep timer fades the volume out over this last stretch before stopping playback.
+const SLEEP_FADE: Duration = Duration::from_secs(10);
+// An alarm that came due longer ago than this, say while the app was closed, is dropped.
+const ALARM_MISSED_AFTER: Duration = Duration::from_mins(10);
+// How long an alarm takes to bring the volume up from silence.
+const ALARM_RAMP: Duration = Duration::from_mins(1);
+const SLEEP_TIMER_STEPS: [Duration; 4] = [
+    Duration::from_mins(15),
+    Duration::from_mins(30),
+    Duration::from_mins(45),
+    Duration::from_hours(1),
+];
+
 #[derive(Clone)]
 pub struct Controller {
     pub state: Entity<AppState>,
@@ -59,6 +84,9 @@ pub struct Controller {
     // Lyrics manager channel
     pub lyrics_manager_tx: Sender<LyricsCommand>,
     pub lyrics_manager_rx: Receiver<LyricsEvent>,
+
+    // Engine supervisor health channel
+    pub supervisor_rx: Receiver<SupervisorEvent>,
 }
 
 impl Controller {
@@ -78,6 +106,7 @@ impl Controller {
         system_integration_rx: Receiver<SystemIntegrationEvent>,
         lyrics_manager_tx: Sender<LyricsCommand>,
         lyrics_manager_rx: Receiver<LyricsEvent>,
+        supervisor_rx: Receiver<SupervisorEvent>,
     ) -> Self {
         Controller {
             state,
@@ -93,6 +122,7 @@ impl Controller {
             system_integration_rx,
             lyrics_manager_tx,
             lyrics_manager_rx,
+            supervisor_rx,
         }
     }
 
@@ -102,18 +132,70 @@ impl Controller {
             && let Some(source) = track.get_valid_source()
         {
             self.audio_tx
-                .send(AudioCommand::Load(*id, source.path.clone()))
-                .ok();
-            self.image_processor_tx
-                .send(ImageProcessorCommand::GetCurrentAlbumArt(
-                    *id,
-                    source.path.clone(),
-                ))
+                .send(AudioCommand::Load(*id, source.clone(), track.replay_gain))
                 .ok();
+
+            if let Some(path) = source.path() {
+                self.image_processor_tx
+                    .send(ImageProcessorCommand::GetCurrentAlbumArt(
+                        *id,
+                        path.to_path_buf(),
+                    ))
+                    .ok();
+            }
         }
     }
 
+    pub fn mark_unplayable(&self, id: TrackId, location: &SourceLocation, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            if let Some(track) = this.library.tracks.get_mut(&id)
+                && let Some(index) = track
+                    .sources
+                    .iter()
+                    .position(|source| !source.unplayable && source.location == *location)
+            {
+                Arc::make_mut(track).sources[index].unplayable = true;
+                cx.notify();
+            }
+        });
+    }
+
+    /// Drops the tracks among `ids` that have no source left on disk, from the library and
+    /// from every playlist.
+    pub fn remove_lost_tracks(&self, ids: &[TrackId], cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            for id in ids {
+                let gone = this
+                    .library
+                    .tracks
+                    .get(id)
+                    .is_some_and(|track| track.sources.iter().all(|s| !s.is_available()));
+
+                if !gone {
+                    continue;
+                }
+
+                this.library.tracks.remove(id);
+                for playlist in this.library.playlists.values_mut() {
+                    playlist.tracks.retain(|tid| tid != id);
+                }
+            }
+
+            cx.notify();
+        });
+        let state = self.state.read(cx).library.clone();
+        let _ = self.cacher_tx.send(CacherCommand::WriteLibraryState(state));
+    }
+
     pub fn load_queue_current(&self, cx: &App) {
+        self.send_queue_current(None, cx);
+    }
+
+    pub fn cue_queue_current(&self, position: Duration, cx: &App) {
+        self.send_queue_current(Some(position), cx);
+    }
+
+    fn send_queue_current(&self, cue_at: Option<Duration>, cx: &App) {
         let state = self.state.read(cx);
 
         if let Some(track_id) = state.queue.get_id(state.playback.current_index)
@@ -121,19 +203,177 @@ impl Controller {
             && let Some(source) = track.get_valid_source()
         {
             self.audio_tx
-                .send(AudioCommand::Load(track_id, source.path.clone()))
+                .send(match cue_at {
+                    Some(position) => {
+                        AudioCommand::Cue(track_id, source.clone(), track.replay_gain, position)
+                    }
+                    None => AudioCommand::Load(track_id, source.clone(), track.replay_gain),
+                })
                 .ok();
-            self.image_processor_tx
-                .send(ImageProcessorCommand::GetCurrentAlbumArt(
+
+            if let Some(path) = source.path() {
+                self.image_processor_tx
+                    .send(ImageProcessorCommand::GetCurrentAlbumArt(
+                        track_id,
+                        path.to_path_buf(),
+                    ))
+                    .ok();
+            }
+        }
+    }
+
+    /// Pushes the persisted playback settings and the current track into the audio engine,
+    /// on launch and after the engine has been restarted.
+    pub fn restore_audio(&self, cx: &mut App) {
+        let playback = self.state.read(cx).playback.clone();
+
+        self.set_output_device(playback.output_device.clone(), cx);
+        self.set_position_interval(playback.position_interval, cx);
+        self.set_crossfade(playback.crossfade, playback.crossfade_curve, cx);
+        self.set_replay_gain_mode(playback.replay_gain_mode, cx);
+        self.set_speed(playback.speed, playback.preserve_pitch, cx);
+        self.apply_eq(cx);
+        self.set_volume(playback.volume, cx);
+
+        if playback.current.is_some() {
+            self.cue_queue_current(playback.position, cx);
+
+            if playback.status == PlaybackStatus::Playing {
+                self.play();
+            }
+        }
+    }
+
+    /// Re-sends whatever a restarted engine may have dropped along with its old instance.
+    pub fn recover_engine(&self, engine: Engine, cx: &mut App) {
+        let state = self.state.read(cx).clone();
+        let current = state.playback.current;
+        let current_track = current.and_then(|id| state.library.tracks.get(&id));
+
+        let fetching_lyrics = {
+            let lyrics = cx.global::<LyricsState>().0.read(cx);
+            lyrics.status == LyricsStatus::Fetching && lyrics.track_id == current
+        };
+
+        match engine {
+            Engine::Audio => self.restore_audio(cx),
+            Engine::Scanner => {
+                let scanning_status = cx.global::<ScanningStatus>().0.clone();
+
+                scanning_status.update(cx, |this, cx| {
+                    this.is_scanning = false;
+                    this.is_discovering = false;
+                    this.is_processing = false;
+                    cx.notify();
+                });
+
+                self.watch_folders(cx);
+
+                // Waveform and loudness jobs died with the scanner; asking the cacher again
+                // falls through to a fresh analysis when nothing was written.
+                if let Some(id) = current
+                    && state.playback.waveform.is_none()
+                {
+                    self.cacher_tx.send(CacherCommand::GetWaveform(id)).ok();
+                }
+            }
+            Engine::Cacher => {
+                let _ = self
+                    .cacher_tx
+                    .send(CacherCommand::WriteLibraryState(state.library.clone()));
+                let _ = self
+                    .cacher_tx
+                    .send(CacherCommand::WriteQueueState(state.queue.clone()));
+                let _ = self
+                    .cacher_tx
+                    .send(CacherCommand::WritePlaybackState(state.playback.clone()));
+                self.write_equalizer_state(cx);
+                self.write_bookmarks(cx);
+
+                cx.global_mut::<ImageCache>().inflight.clear();
+
+                if let Some(id) = current {
+                    if fetching_lyrics {
+                        self.cacher_tx.send(CacherCommand::GetLyrics(id)).ok();
+                    }
+                    if state.playback.waveform.is_none() {
+                        self.cacher_tx.send(CacherCommand::GetWaveform(id)).ok();
+                    }
+                }
+            }
+            Engine::ImageProcessor => {
+                if let Some(track) = current_track
+                    && let Some(path) = track.get_valid_source().and_then(TrackSource::path)
+                {
+                    self.image_processor_tx
+                        .send(ImageProcessorCommand::GetCurrentAlbumArt(
+                            track.id,
+                            path.to_path_buf(),
+                        ))
+                        .ok();
+                }
+            }
+            Engine::LyricsManager => {
+                if let Some(track) = current_track
+                    && fetching_lyrics
+                {
+                    self.get_lyrics(
+                        track.id,
+                        &track.title,
+                        &track.artist,
+                        &track.album,
+                        track.duration,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn preload_queue_next(&self, cx: &App) {
+        // Nothing comes next when the sleep timer stops playback at the end of this track.
+        if self.sleep_ends_with_track(cx) {
+            return;
+        }
+
+        let state = self.state.read(cx);
+
+        let next_index = if state.playback.repeat {
+            state.playback.current_index
+        } else {
+            state.playback.current_index + 1
+        };
+
+        if let Some(track_id) = state.queue.get_id(next_index)
+            && let Some(track) = state.library.tracks.get(&track_id)
+            && let Some(source) = track.get_valid_source()
+        {
+            self.audio_tx
+                .send(AudioCommand::Preload(
                     track_id,
-                    source.path.clone(),
+                    source.clone(),
+                    track.replay_gain,
                 ))
                 .ok();
         }
     }
 
-    pub fn get_pos(&self) {
-        let _ = self.audio_tx.send(AudioCommand::GetPosition);
+    // Lines up whatever the queue leads to now, in place of the track preloaded before.
+    fn requeue_next(&self, cx: &App) {
+        let _ = self.audio_tx.send(AudioCommand::ClearPreload);
+        self.preload_queue_next(cx);
+    }
+
+    pub fn set_waveform(&self, id: TrackId, waveform: &Arc<Waveform>, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            if this.playback.current == Some(id) {
+                this.playback.waveform = Some(waveform.clone());
+                cx.notify();
+            }
+        });
+    }
+
+    pub fn set_analysis(&self, enabled: bool) {
+        let _ = self.audio_tx.send(AudioCommand::SetAnalysis(enabled));
     }
 
     pub fn scan_dir(&self, path: PathBuf) {
@@ -186,11 +426,46 @@ impl Controller {
             this.playback.current_playlist = None;
         });
 
-        self.load_queue_current(cx);
+        let resume_at = self.state.read(cx).resume.positions.get(&track_id).copied();
+
+        if let Some(position) = resume_at {
+            self.cue_queue_current(position, cx);
+            self.play();
+        } else {
+            self.load_queue_current(cx);
+        }
+
         let state = self.state.read(cx).queue.clone();
         let _ = self.cacher_tx.send(CacherCommand::WriteQueueState(state));
     }
 
+    /// Has the scanner follow the folder behind every folder playlist from here on.
+    pub fn watch_folders(&self, cx: &App) {
+        for (playlist, path) in self.folder_roots(cx) {
+            let _ = self
+                .scanner_tx
+                .send(ScannerCommand::WatchFolder(playlist, path));
+        }
+    }
+
+    /// Walks every folder playlist again, picking up changed files and dropping missing ones.
+    pub fn rescan_library(&self, cx: &App) {
+        let _ = self
+            .scanner_tx
+            .send(ScannerCommand::Rescan(self.folder_roots(cx)));
+    }
+
+    fn folder_roots(&self, cx: &App) -> Vec<(PlaylistId, PathBuf)> {
+        self.state
+            .read(cx)
+            .library
+            .playlists
+            .values()
+            .filter(|playlist| playlist.source == PlaylistSource::Folder)
+            .filter_map(|playlist| Some((playlist.id, playlist.folder_path.clone()?)))
+            .collect()
+    }
+
     pub fn scan_track(&self, path: PathBuf) {
         let _ = self.scanner_tx.send(ScannerCommand::ScanTrack(path));
     }
@@ -211,6 +486,8 @@ impl Controller {
         self.state.update(cx, |this, _| {
             this.playback.repeat = !this.playback.repeat;
         });
+        self.requeue_next(cx);
+
         let state = self.state.read(cx).playback.clone();
         let _ = self
             .cacher_tx
@@ -252,6 +529,329 @@ impl Controller {
             .send(CacherCommand::WritePlaybackState(state));
     }
 
+    pub fn set_crossfade(&self, duration: Duration, curve: FadeCurve, cx: &mut App) {
+        self.state.update(cx, |this, _| {
+            this.playback.crossfade = duration;
+            this.playback.crossfade_curve = curve;
+        });
+
+        let _ = self
+            .audio_tx
+            .send(AudioCommand::SetCrossfade(duration, curve));
+        self.requeue_next(cx);
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn set_replay_gain_mode(&self, mode: ReplayGainMode, cx: &mut App) {
+        self.state.update(cx, |this, _| {
+            this.playback.replay_gain_mode = mode;
+        });
+
+        let _ = self.audio_tx.send(AudioCommand::SetReplayGainMode(mode));
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn set_resume_on_launch(&self, mode: ResumeOnLaunch, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            this.playback.resume_on_launch = mode;
+            cx.notify();
+        });
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn set_resume_threshold(&self, threshold: Duration, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            this.playback.resume_threshold = threshold;
+            cx.notify();
+        });
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn set_speed(&self, speed: f32, preserve_pitch: bool, cx: &mut App) {
+        self.state.update(cx, |this, _| {
+            this.playback.speed = speed;
+            this.playback.preserve_pitch = preserve_pitch;
+        });
+
+        let _ = self.audio_tx.send(AudioCommand::SetSpeed(speed));
+        let _ = self
+            .audio_tx
+            .send(AudioCommand::SetPreservePitch(preserve_pitch));
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn set_position_interval(&self, interval: Duration, cx: &mut App) {
+        self.state.update(cx, |this, _| {
+            this.playback.position_interval = interval;
+        });
+
+        let _ = self
+            .audio_tx
+            .send(AudioCommand::SetPositionInterval(interval));
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn set_output_device(&self, name: Option<String>, cx: &mut App) {
+        self.state.update(cx, |this, _| {
+            this.playback.output_device.clone_from(&name);
+        });
+
+        let _ = self.audio_tx.send(AudioCommand::SetDevice(name));
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn apply_eq(&self, cx: &App) {
+        let state = self.state.read(cx);
+        let equalizer = &state.equalizer;
+
+        let bands = state
+            .playback
+            .current
+            .and_then(|id| state.library.tracks.get(&id))
+            .and_then(|track| equalizer.override_for(track))
+            .map_or_else(|| equalizer.bands.clone(), |preset| preset.bands.clone());
+
+        let _ = self.audio_tx.send(AudioCommand::SetEq(bands));
+        let _ = self
+            .audio_tx
+            .send(AudioCommand::SetEqEnabled(equalizer.enabled));
+    }
+
+    pub fn set_eq_enabled(&self, enabled: bool, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            this.equalizer.enabled = enabled;
+            cx.notify();
+        });
+
+        self.apply_eq(cx);
+        self.write_equalizer_state(cx);
+    }
+
+    pub fn set_eq_band_gain(&self, index: usize, gain: f32, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            if let Some(band) = this.equalizer.bands.get_mut(index) {
+                band.gain = gain;
+                this.equalizer.preset = None;
+            }
+            cx.notify();
+        });
+
+        self.apply_eq(cx);
+        self.write_equalizer_state(cx);
+    }
+
+    pub fn load_eq_preset(&self, name: &str, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            if let Some(preset) = this.equalizer.preset(name) {
+                this.equalizer.bands = preset.bands.clone();
+                this.equalizer.preset = Some(preset.name.clone());
+            }
+            cx.notify();
+        });
+
+        self.apply_eq(cx);
+        self.write_equalizer_state(cx);
+    }
+
+    pub fn save_eq_preset(&self, name: &str, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            let bands = this.equalizer.bands.clone();
+
+            if let Some(preset) = this.equalizer.presets.iter_mut().find(|p| p.name == name) {
+                preset.bands = bands;
+            } else {
+                this.equalizer.presets.push(EqPreset {
+                    name: name.to_string(),
+                    bands,
+                });
+            }
+
+            this.equalizer.preset = Some(name.to_string());
+            cx.notify();
+        });
+
+        self.write_equalizer_state(cx);
+    }
+
+    pub fn set_track_eq_preset(&self, id: TrackId, preset: Option<String>, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            match preset {
+                Some(name) => this.equalizer.track_presets.insert(id, name),
+                None => this.equalizer.track_presets.remove(&id),
+            };
+            cx.notify();
+        });
+
+        self.apply_eq(cx);
+        self.write_equalizer_state(cx);
+    }
+
+    pub fn set_album_eq_preset(&self, album: String, preset: Option<String>, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            match preset {
+                Some(name) => this.equalizer.album_presets.insert(album, name),
+                None => this.equalizer.album_presets.remove(&album),
+            };
+            cx.notify();
+        });
+
+        self.apply_eq(cx);
+        self.write_equalizer_state(cx);
+    }
+
+    fn write_equalizer_state(&self, cx: &App) {
+        let state = self.state.read(cx).equalizer.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WriteEqualizerState(state));
+    }
+
+    pub fn cycle_ab_loop(&self, cx: &mut App) {
+        let playback = self.state.read(cx).playback.clone();
+
+        match (playback.loop_start, playback.loop_end) {
+            (None, _) => {
+                self.state.update(cx, |this, cx| {
+                    this.playback.loop_start = Some(playback.position);
+                    cx.notify();
+                });
+            }
+            (Some(start), None) if playback.position > start => {
+                self.state.update(cx, |this, cx| {
+                    this.playback.loop_end = Some(playback.position);
+                    cx.notify();
+                });
+
+                let _ = self.audio_tx.send(AudioCommand::SetLoop {
+                    start,
+                    end: playback.position,
+                });
+            }
+            _ => self.clear_ab_loop(cx),
+        }
+    }
+
+    pub fn clear_ab_loop(&self, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            this.playback.loop_start = None;
+            this.playback.loop_end = None;
+            cx.notify();
+        });
+
+        let _ = self.audio_tx.send(AudioCommand::ClearLoop);
+    }
+
+    pub fn add_bookmark(&self, name: String, cx: &mut App) {
+        let playback = &self.state.read(cx).playback;
+        let Some(id) = playback.current else {
+            return;
+        };
+        let position = playback.position;
+
+        self.state.update(cx, |this, cx| {
+            let bookmarks = this.bookmarks.tracks.entry(id).or_default();
+
+            bookmarks.push(Bookmark { name, position });
+            bookmarks.sort_by_key(|bookmark| bookmark.position);
+            cx.notify();
+        });
+
+        self.write_bookmarks(cx);
+    }
+
+    pub fn jump_to_next_bookmark(&self, cx: &App) {
+        let state = self.state.read(cx);
+        let Some(bookmarks) = state
+            .playback
+            .current
+            .and_then(|id| state.bookmarks.tracks.get(&id))
+        else {
+            return;
+        };
+
+        if let Some(bookmark) = next_bookmark(bookmarks, state.playback.position) {
+            self.seek(bookmark.position);
+        }
+    }
+
+    fn write_bookmarks(&self, cx: &App) {
+        let state = self.state.read(cx).bookmarks.clone();
+        let _ = self.cacher_tx.send(CacherCommand::WriteBookmarks(state));
+    }
+
+    /// Remembers how far into the current track playback got, if it's long enough to be
+    /// worth resuming. Nearly finished tracks are forgotten so they start over.
+    pub fn record_resume_position(&self, position: Duration, cx: &mut App) {
+        let state = self.state.read(cx);
+        let threshold = state.playback.resume_threshold;
+
+        let Some(id) = state.playback.current else {
+            return;
+        };
+        let Some(track) = state.library.tracks.get(&id) else {
+            return;
+        };
+
+        if threshold.is_zero() || track.duration < threshold {
+            return;
+        }
+
+        let finished = track.duration.saturating_sub(position) < RESUME_END_MARGIN;
+        let saved = state.resume.positions.get(&id).copied();
+
+        let changed = match saved {
+            Some(_) if finished => true,
+            Some(saved) => saved.abs_diff(position) >= RESUME_SAVE_STEP,
+            None => !finished && position >= RESUME_SAVE_STEP,
+        };
+
+        if !changed {
+            return;
+        }
+
+        self.state.update(cx, |this, cx| {
+            if finished {
+                this.resume.positions.remove(&id);
+            } else {
+                this.resume.positions.insert(id, position);
+            }
+            cx.notify();
+        });
+
+        let state = self.state.read(cx).resume.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WriteResumePositions(state));
+    }
+
     pub fn set_shuffle(&self, cx: &mut App) {
         self.state.update(cx, |this, _| {
             this.playback.shuffling = !this.playback.shuffling;
@@ -280,6 +880,8 @@ impl Controller {
             }
         });
 
+        self.requeue_next(cx);
+
         let state = self.state.read(cx).clone();
         let _ = self
             .cacher_tx
@@ -290,6 +892,24 @@ impl Controller {
     }
 
     pub fn next(&self, cx: &mut App) {
+        if let Some(start) = self.chapter_step(true, cx) {
+            self.seek(start);
+            return;
+        }
+
+        self.next_track(cx);
+    }
+
+    pub fn prev(&self, cx: &mut App) {
+        if let Some(start) = self.chapter_step(false, cx) {
+            self.seek(start);
+            return;
+        }
+
+        self.prev_track(cx);
+    }
+
+    pub fn next_track(&self, cx: &mut App) {
         self.state.update(cx, |this, _| {
             this.playback.current_index =
                 (this.playback.current_index + 1).clamp(0, this.library.tracks.len());
@@ -305,7 +925,7 @@ impl Controller {
             .cacher_tx
             .send(CacherCommand::WritePlaybackState(state.playback));
     }
-    pub fn prev(&self, cx: &mut App) {
+    pub fn prev_track(&self, cx: &mut App) {
         self.state.update(cx, |this, _| {
             this.playback.current_index = this.playback.current_index.saturating_sub(1);
         });
@@ -325,8 +945,232 @@ impl Controller {
         let _ = self.audio_tx.send(AudioCommand::Seek(pos));
     }
 
-    pub fn check_track_ended(&self) {
-        let _ = self.audio_tx.send(AudioCommand::CheckTrackEnded);
+    /// Where next/previous should seek to within the current track when skipping by
+    /// chapter, or `None` once they should move on to another track.
+    fn chapter_step(&self, forward: bool, cx: &App) -> Option<Duration> {
+        let state = self.state.read(cx);
+
+        if !state.playback.skip_chapters {
+            return None;
+        }
+
+        let track = state
+            .playback
+            .current
+            .and_then(|id| state.library.tracks.get(&id))?;
+        let position = state.playback.position;
+        let current = track.chapter_at(position);
+
+        if forward {
+            let next = current.map_or(0, |index| index + 1);
+            return track.chapters.get(next).map(|chapter| chapter.start);
+        }
+
+        let index = current?;
+        let start = track.chapters[index].start;
+
+        if position.saturating_sub(start) >= CHAPTER_RESTART_AFTER {
+            return Some(start);
+        }
+
+        index
+            .checked_sub(1)
+            .map(|index| track.chapters[index].start)
+    }
+
+    pub fn set_skip_chapters(&self, skip: bool, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            this.playback.skip_chapters = skip;
+            cx.notify();
+        });
+
+        let state = self.state.read(cx).playback.clone();
+        let _ = self
+            .cacher_tx
+            .send(CacherCommand::WritePlaybackState(state));
+    }
+
+    pub fn set_sleep_timer(&self, timer: Option<SleepTimer>, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            this.playback.sleep_timer = timer;
+            cx.notify();
+        });
+
+        // Undoes a fade that was already under way.
+        let _ = self
+            .audio_tx
+            .send(AudioCommand::SetVolume(self.output_volume(cx)));
+
+        self.requeue_next(cx);
+    }
+
+    /// Steps the sleep timer from off through the preset durations, then the end of the
+    /// current track and the end of the queue.
+    pub fn cycle_sleep_timer(&self, cx: &mut App) {
+        let after = |duration| SleepTimer::After {
+            duration,
+            started: Instant::now(),
+        };
+
+        let next = match self.state.read(cx).playback.sleep_timer {
+            None => Some(after(SLEEP_TIMER_STEPS[0])),
+            Some(SleepTimer::After { duration, .. }) => Some(
+                SLEEP_TIMER_STEPS
+                    .iter()
+                    .copied()
+                    .find(|&step| step > duration)
+                    .map_or(SleepTimer::EndOfTrack, after),
+            ),
+            Some(SleepTimer::EndOfTrack) => Some(SleepTimer::EndOfQueue),
+            Some(SleepTimer::EndOfQueue) => None,
+        };
+
+        self.set_sleep_timer(next, cx);
+    }
+
+    /// How long until the sleep timer stops playback, or `None` if it isn't due to
+    /// before the current track ends.
+    #[must_use]
+    pub fn sleep_remaining(&self, cx: &App) -> Option<Duration> {
+        let state = self.state.read(cx);
+        let playback = &state.playback;
+
+        match playback.sleep_timer? {
+            SleepTimer::After { duration, started } => {
+                Some(duration.saturating_sub(started.elapsed()))
+            }
+            _ if !self.sleep_ends_with_track(cx) => None,
+            _ => {
+                let track = playback
+                    .current
+                    .and_then(|id| state.library.tracks.get(&id))
+                    .filter(|track| !track.duration.is_zero())?;
+
+                let left = track.duration.saturating_sub(playback.position);
+
+                Some(left.div_f32(playback.speed))
+            }
+        }
+    }
+
+    /// Whether the sleep timer runs out together with the current track.
+    #[must_use]
+    pub fn sleep_ends_with_track(&self, cx: &App) -> bool {
+        let state = self.state.read(cx);
+        let playback = &state.playback;
+
+        match playback.sleep_timer {
+            Some(SleepTimer::EndOfTrack) => true,
+            Some(SleepTimer::EndOfQueue) => {
+                !playback.repeat && playback.current_index + 1 >= state.queue.order.len()
+            }
+            Some(SleepTimer::After { .. }) | None => false,
+        }
+    }
+
+    /// Fades the volume out as the sleep timer runs down, and pauses once it has run out.
+    /// A timer ending with the track is left to stop playback when the track ends.
+    pub fn tick_sleep_timer(&self, cx: &mut App) {
+        let Some(remaining) = self.sleep_remaining(cx) else {
+            return;
+        };
+
+        if remaining.is_zero() {
+            if self.sleep_ends_with_track(cx) {
+                return;
+            }
+
+            self.pause();
+            self.set_sleep_timer(None, cx);
+        } else if remaining < SLEEP_FADE {
+            let volume =
+                self.output_volume(cx) * remaining.as_secs_f32() / SLEEP_FADE.as_secs_f32();
+            let _ = self.audio_tx.send(AudioCommand::SetVolume(volume));
+        }
+    }
+
+    fn output_volume(&self, cx: &App) -> f32 {
+        let playback = &self.state.read(cx).playback;
+
+        if playback.mute { 0.0 } else { playback.volume }
+    }
+
+    pub fn set_alarm(&self, alarm: Option<Alarm>, cx: &mut App) {
+        self.state.update(cx, |this, cx| {
+            this.alarm.alarm = alarm;
+            cx.notify();
+        });
+
+        let state = self.state.read(cx).alarm.clone();
+        let _ = self.cacher_tx.send(CacherCommand::WriteAlarm(state));
+    }
+
+    /// Starts the alarm's playlist once it's due and ramps the volume up afterwards.
+    /// Returns the volume whenever the ramp changed it.
+    pub fn tick_alarm(&self, cx: &mut App) -> Option<f32> {
+        let state = self.state.read(cx).alarm.clone();
+
+        if let Some(ramp) = state.ramp {
+            return self.ramp_alarm_volume(ramp, cx);
+        }
+
+        let alarm = state.alarm?;
+        let late = SystemTime::now().duration_since(alarm.at).ok()?;
+
+        self.set_alarm(None, cx);
+
+        let exists = self
+            .state
+            .read(cx)
+            .library
+            .playlists
+            .contains_key(&alarm.playlist);
+
+        if late > ALARM_MISSED_AFTER || !exists {
+            return None;
+        }
+
+        let playback = self.state.read(cx).playback.clone();
+
+        if playback.mute {
+            self.set_mute(cx);
+        }
+
+        self.set_volume(0.0, cx);
+        self.load_playlist(alarm.playlist, cx);
+
+        self.state.update(cx, |this, _| {
+            this.alarm.ramp = Some(AlarmRamp {
+                started: Instant::now(),
+                target: playback.volume,
+                last: 0.0,
+            });
+        });
+
+        Some(0.0)
+    }
+
+    fn ramp_alarm_volume(&self, ramp: AlarmRamp, cx: &mut App) -> Option<f32> {
+        let volume = self.state.read(cx).playback.volume;
+
+        // The volume was changed by hand, so it's no longer the ramp's to set.
+        if (volume - ramp.last).abs() > f32::EPSILON {
+            self.state.update(cx, |this, _| this.alarm.ramp = None);
+            return None;
+        }
+
+        let progress = (ramp.started.elapsed().as_secs_f32() / ALARM_RAMP.as_secs_f32()).min(1.0);
+        let volume = ramp.target * progress;
+
+        self.set_volume(volume, cx);
+        self.state.update(cx, |this, _| {
+            this.alarm.ramp = (progress < 1.0).then_some(AlarmRamp {
+                last: volume,
+                ..ramp
+            });
+        });
+
+        Some(volume)
     }
 
     pub fn load_cached_app_state(&self) {
@@ -344,8 +1188,8 @@ impl Controller {
             if let Some(track) = tracks.get(tid) {
                 if let Some(image_id) = track.image_id {
                     cache_ids.push(image_id);
-                } else if let Some(source) = track.get_valid_source() {
-                    scan_jobs.insert((track.id, source.path.clone()));
+                } else if let Some(path) = track.get_valid_source().and_then(TrackSource::path) {
+                    scan_jobs.insert((track.id, path.to_path_buf()));
                 }
             }
         }
@@ -435,9 +1279,9 @@ pub fn pick_playlist_thumbnail_tracks<S: ::std::hash::BuildHasher>(
     for id in candidates {
         if let Some(track) = library_tracks.get(&id)
             && albums.insert(track.album.clone())
-            && let Some(source) = track.get_valid_source()
+            && let Some(path) = track.get_valid_source().and_then(TrackSource::path)
         {
-            chosen.push(source.path.clone());
+            chosen.push(path.to_path_buf());
         }
 
         if chosen.len() == count {
@@ -453,12 +1297,22 @@ pub fn pick_playlist_thumbnail_tracks<S: ::std::hash::BuildHasher>(
 
             if let Some(track) = library_tracks.get(id)
                 && albums.insert(track.album.clone())
-                && let Some(source) = track.get_valid_source()
+                && let Some(path) = track.get_valid_source().and_then(TrackSource::path)
             {
-                chosen.push(source.path.clone());
+                chosen.push(path.to_path_buf());
             }
         }
     }
 
     chosen
 }
+
+#[must_use]
+pub fn next_bookmark(bookmarks: &[Bookmark], position: Duration) -> Option<&Bookmark> {
+    let position = position + Duration::from_millis(500);
+
+    bookmarks
+        .iter()
+        .find(|bookmark| bookmark.position > position)
+        .or_else(|| bookmarks.first())
+}
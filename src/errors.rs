@@ -35,10 +35,24 @@ pub enum ScannerError {
     IoError(#[from] std::io::Error),
     #[error("Lofty Error occurred: `{0}`")]
     LoftyError(#[from] LoftyError),
+    #[error("Rodio Decoder Error occurred: `{0}`")]
+    RodioDecoderError(#[from] rodio::decoder::DecoderError),
+    #[error("Error occurred while seeking: `{0}`")]
+    SeekError(#[from] SeekError),
+    #[error("Invalid CUE sheet: `{0}`")]
+    CueSheet(String),
+    #[error("Invalid station file: `{0}`")]
+    StationFile(String),
+    #[error("Network streams can't be scanned")]
+    StreamSource,
+    #[error("Notify Error occurred: `{0}`")]
+    NotifyError(#[from] notify::Error),
     #[error("SystemTime Error occurred: `{0}`")]
     SystemTimeError(#[from] SystemTimeError),
     #[error("Recv Error occurred: `{0}`")]
     RecvError(#[from] RecvError),
+    #[error("Worker thread panicked: `{0}`")]
+    WorkerPanicked(String),
 }
 
 #[derive(Error, Debug)]
@@ -49,6 +63,8 @@ pub enum ImageProcessorError {
     GarbSizeError(#[from] garb::SizeError),
     #[error("Recv Error occurred: `{0}`")]
     RecvError(#[from] RecvError),
+    #[error("Worker thread panicked: `{0}`")]
+    WorkerPanicked(String),
 }
 
 #[derive(Error, Debug)]
@@ -69,6 +85,8 @@ pub enum CacherError {
     RonError(#[from] ron::Error),
     #[error("RON Spanned Error occurred: `{0}`")]
     RonSpannedError(#[from] ron::de::SpannedError),
+    #[error("Worker thread panicked: `{0}`")]
+    WorkerPanicked(String),
 }
 
 #[derive(Error, Debug)]
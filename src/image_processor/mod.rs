@@ -1,4 +1,4 @@
-use crate::app::AppPaths;
+use crate::app::{AppPaths, spawn_pool_worker};
 use crate::cacher::{Cacher, paths::CachePaths};
 use crate::controller::commands::ImageProcessorCommand;
 use crate::controller::events::ImageProcessorEvent;
@@ -58,15 +58,23 @@ impl ImageProcessor {
         let (thumb_tx, thumb_rx) = crossbeam_channel::unbounded();
         let (album_art_tx, album_art_rx) = crossbeam_channel::unbounded();
         let (playlist_thumb_tx, playlist_thumb_rx) = crossbeam_channel::unbounded();
+        let (crashed_tx, crashed_rx) = crossbeam_channel::unbounded();
 
-        self.spawn_thumbnail_workers(&thumb_rx, thumbnail_workers);
-        self.spawn_album_art_worker(album_art_rx);
-        self.spawn_playlist_thumbnail_worker(playlist_thumb_rx);
+        self.spawn_thumbnail_workers(&thumb_rx, thumbnail_workers, &crashed_tx);
+        self.spawn_album_art_worker(album_art_rx, &crashed_tx);
+        self.spawn_playlist_thumbnail_worker(playlist_thumb_rx, &crashed_tx);
 
         let mut inflight_playlists = HashSet::new();
 
         loop {
-            match self.rx.recv()? {
+            let command = select! {
+                recv(self.rx) -> command => command?,
+                recv(crashed_rx) -> message => {
+                    return Err(ImageProcessorError::WorkerPanicked(message?));
+                }
+            };
+
+            match command {
                 ImageProcessorCommand::GetThumbnails(images, kind) => {
                     let cached_thumbnails_index = Arc::new(Cacher::build_cached_thumbnails_index(
                         self.app_paths.cache.as_path(),
@@ -96,7 +104,12 @@ impl ImageProcessor {
         }
     }
 
-    fn spawn_thumbnail_workers(&self, thumb_rx: &Receiver<ImageJob>, workers: usize) {
+    fn spawn_thumbnail_workers(
+        &self,
+        thumb_rx: &Receiver<ImageJob>,
+        workers: usize,
+        crashed: &Sender<String>,
+    ) {
         let ticker = tick(Duration::from_millis(128));
 
         for _ in 0..workers {
@@ -105,7 +118,7 @@ impl ImageProcessor {
             let thumb_rx = thumb_rx.clone();
             let seen_images = self.seen_images.clone();
 
-            std::thread::spawn(move || {
+            spawn_pool_worker("image thumbnails", crashed, move || {
                 let mut image_batch = HashMap::with_capacity(64);
                 let mut lookup_batch = HashMap::with_capacity(64);
                 let mut last_kind = ImageKind::ThumbnailSmall;
@@ -113,7 +126,13 @@ impl ImageProcessor {
                 loop {
                     select! {
                         recv(thumb_rx) -> job => {
-                            if let Ok(ImageJob::Thumbnail(id, path, kind, cached_images)) = job &&
+                            // The engine is gone, e.g. crashed and being restarted with a
+                            // fresh pool.
+                            let Ok(job) = job else {
+                                break;
+                            };
+
+                            if let ImageJob::Thumbnail(id, path, kind, cached_images) = job &&
                                  let Ok(Some(bytes)) = metadata::read_album_art(&path) && let Ok(hash) = ImageId::generate(&bytes) {
                                     lookup_batch.insert(id, hash);
                                     last_kind = kind;
@@ -150,11 +169,11 @@ impl ImageProcessor {
         }
     }
 
-    fn spawn_album_art_worker(&self, album_art_rx: Receiver<ImageJob>) {
+    fn spawn_album_art_worker(&self, album_art_rx: Receiver<ImageJob>, crashed: &Sender<String>) {
         let events_tx = self.tx.clone();
         let cache_path = self.app_paths.cache.clone();
 
-        std::thread::spawn(move || {
+        spawn_pool_worker("image album art", crashed, move || {
             while let Ok(ImageJob::AlbumArt(id, path)) = album_art_rx.recv() {
                 match metadata::read_album_art(&path) {
                     Ok(Some(image)) => {
@@ -187,10 +206,14 @@ impl ImageProcessor {
         });
     }
 
-    fn spawn_playlist_thumbnail_worker(&self, playlist_thumb_rx: Receiver<ImageJob>) {
+    fn spawn_playlist_thumbnail_worker(
+        &self,
+        playlist_thumb_rx: Receiver<ImageJob>,
+        crashed: &Sender<String>,
+    ) {
         let events_tx = self.tx.clone();
 
-        std::thread::spawn(move || {
+        spawn_pool_worker("image playlist thumbnails", crashed, move || {
             while let Ok(ImageJob::PlaylistThumbnail(id, tracks)) = playlist_thumb_rx.recv() {
                 let mut images = Vec::with_capacity(4);
 
@@ -0,0 +1,157 @@
+use crate::controller::state::Chapter;
+use lofty::config::ParseOptions;
+use lofty::file::{AudioFile, FileType};
+use lofty::flac::FlacFile;
+use lofty::ogg::{OpusFile, VorbisComments, VorbisFile};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+// Nero chapter starts are counted in 100ns units.
+const NERO_UNITS_PER_SECOND: u64 = 10_000_000;
+
+/// Reads the chapter markers of a file, sorted by start. Empty when it has none.
+#[must_use]
+pub fn read(path: &Path, file_type: FileType) -> Vec<Chapter> {
+    let mut chapters = match file_type {
+        FileType::Mp4 => read_nero(path).unwrap_or_default(),
+        _ => read_vorbis_comments(path, file_type)
+            .map(|comments| from_vorbis_comments(&comments))
+            .unwrap_or_default(),
+    };
+
+    chapters.sort_by_key(|chapter| chapter.start);
+    chapters
+}
+
+fn read_vorbis_comments(path: &Path, file_type: FileType) -> Option<VorbisComments> {
+    let mut file = File::open(path).ok()?;
+    let options = ParseOptions::new().read_properties(false);
+
+    match file_type {
+        FileType::Flac => FlacFile::read_from(&mut file, options)
+            .ok()?
+            .vorbis_comments()
+            .cloned(),
+        FileType::Vorbis => Some(
+            VorbisFile::read_from(&mut file, options)
+                .ok()?
+                .vorbis_comments()
+                .clone(),
+        ),
+        FileType::Opus => Some(
+            OpusFile::read_from(&mut file, options)
+                .ok()?
+                .vorbis_comments()
+                .clone(),
+        ),
+        _ => None,
+    }
+}
+
+// The de facto scheme is `CHAPTER001=00:01:02.500` with the title in `CHAPTER001NAME`.
+fn from_vorbis_comments(comments: &VorbisComments) -> Vec<Chapter> {
+    comments
+        .items()
+        .filter_map(|(key, value)| {
+            let number = key
+                .to_ascii_uppercase()
+                .strip_prefix("CHAPTER")?
+                .parse::<u32>()
+                .ok()?;
+            let start = parse_timestamp(value)?;
+
+            let title = comments
+                .get(&format!("{key}NAME"))
+                .map_or_else(|| format!("Chapter {number}"), ToString::to_string);
+
+            Some(Chapter { title, start })
+        })
+        .collect()
+}
+
+fn parse_timestamp(value: &str) -> Option<Duration> {
+    let (clock, fraction) = value.trim().split_once('.').unwrap_or((value.trim(), "0"));
+
+    let mut secs = 0u64;
+    for part in clock.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+
+    let millis = format!("{fraction:0<3}").get(..3)?.parse::<u64>().ok()?;
+
+    Some(Duration::from_secs(secs) + Duration::from_millis(millis))
+}
+
+// Nero chapters live in a `chpl` atom under `moov/udta`.
+fn read_nero(path: &Path) -> Option<Vec<Chapter>> {
+    let mut file = File::open(path).ok()?;
+    let end = file.metadata().ok()?.len();
+
+    let (moov, moov_end) = find_atom(&mut file, 0, end, b"moov")?;
+    let (udta, udta_end) = find_atom(&mut file, moov, moov_end, b"udta")?;
+    let (chpl, chpl_end) = find_atom(&mut file, udta, udta_end, b"chpl")?;
+
+    let mut data = vec![0; usize::try_from(chpl_end - chpl).ok()?];
+    file.seek(SeekFrom::Start(chpl)).ok()?;
+    file.read_exact(&mut data).ok()?;
+
+    let version = *data.first()?;
+    // Version 1 has four reserved bytes after the flags.
+    let mut at = if version == 0 { 4 } else { 8 };
+    let count = *data.get(at)?;
+    at += 1;
+
+    let mut chapters = Vec::with_capacity(usize::from(count));
+
+    for _ in 0..count {
+        let start = u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?);
+        let len = usize::from(*data.get(at + 8)?);
+        let title = data.get(at + 9..at + 9 + len)?;
+        at += 9 + len;
+
+        chapters.push(Chapter {
+            title: String::from_utf8_lossy(title).into_owned(),
+            start: Duration::from_nanos(
+                start.saturating_mul(1_000_000_000 / NERO_UNITS_PER_SECOND),
+            ),
+        });
+    }
+
+    Some(chapters)
+}
+
+// Returns the payload range of the first `name` atom between `start` and `end`.
+fn find_atom(file: &mut File, start: u64, end: u64, name: &[u8; 4]) -> Option<(u64, u64)> {
+    let mut at = start;
+
+    while at + 8 <= end {
+        let mut header = [0u8; 8];
+        file.seek(SeekFrom::Start(at)).ok()?;
+        file.read_exact(&mut header).ok()?;
+
+        let size = u64::from(u32::from_be_bytes(header[..4].try_into().ok()?));
+        let (header_len, size) = match size {
+            0 => (8, end - at),
+            1 => {
+                let mut large = [0u8; 8];
+                file.read_exact(&mut large).ok()?;
+                (16, u64::from_be_bytes(large))
+            }
+            size => (8, size),
+        };
+
+        if size < header_len {
+            return None;
+        }
+
+        if &header[4..] == name {
+            return Some((at + header_len, (at + size).min(end)));
+        }
+
+        at += size;
+    }
+
+    None
+}
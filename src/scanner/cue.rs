@@ -0,0 +1,375 @@
+use crate::audio::formats;
+use crate::controller::state::TrackRange;
+use crate::errors::ScannerError;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const FRAMES_PER_SECOND: u64 = 75;
+
+#[derive(Debug, Clone, Default)]
+pub struct CueSheet {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CueTrack {
+    pub file: PathBuf,
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub start: Duration,
+}
+
+/// One cue entry resolved against its neighbours, ready to become a `Track`.
+#[derive(Debug, Clone)]
+pub struct CueEntry {
+    pub file: PathBuf,
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub album: Option<String>,
+    pub start: Duration,
+    // `None` for the last entry of a file, which runs to its end.
+    pub end: Option<Duration>,
+}
+
+#[must_use]
+pub fn is_cue_sheet(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+}
+
+#[allow(clippy::missing_errors_doc)]
+pub fn read(path: &Path) -> Result<CueSheet, ScannerError> {
+    let bytes = std::fs::read(path)?;
+
+    // Older rippers write Latin-1 rather than UTF-8.
+    let text = String::from_utf8(bytes)
+        .unwrap_or_else(|e| e.into_bytes().into_iter().map(char::from).collect());
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    parse(text.trim_start_matches('\u{feff}'), dir)
+}
+
+fn parse(text: &str, dir: &Path) -> Result<CueSheet, ScannerError> {
+    let mut sheet = CueSheet::default();
+    let mut file: Option<PathBuf> = None;
+    let mut track: Option<CueTrack> = None;
+    let mut has_start = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                file = Some(resolve_file(dir, &unquote(rest)));
+            }
+            "TRACK" => {
+                if let Some(track) = track.take().filter(|_| has_start) {
+                    sheet.tracks.push(track);
+                }
+
+                let Some(file) = file.clone() else {
+                    return Err(ScannerError::CueSheet("TRACK before any FILE".into()));
+                };
+
+                let number = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(0);
+
+                has_start = false;
+                track = Some(CueTrack {
+                    file,
+                    number,
+                    title: None,
+                    performer: None,
+                    start: Duration::ZERO,
+                });
+            }
+            "TITLE" => match track.as_mut() {
+                Some(track) => track.title = Some(unquote(rest)),
+                None => sheet.title = Some(unquote(rest)),
+            },
+            "PERFORMER" => match track.as_mut() {
+                Some(track) => track.performer = Some(unquote(rest)),
+                None => sheet.performer = Some(unquote(rest)),
+            },
+            "INDEX" => {
+                let mut parts = rest.split_whitespace();
+
+                if let (Some("01"), Some(time), Some(track)) =
+                    (parts.next(), parts.next(), track.as_mut())
+                {
+                    track.start = parse_time(time).ok_or_else(|| {
+                        ScannerError::CueSheet(format!("invalid INDEX time `{time}`"))
+                    })?;
+                    has_start = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(track) = track.filter(|_| has_start) {
+        sheet.tracks.push(track);
+    }
+
+    if sheet.tracks.is_empty() {
+        return Err(ScannerError::CueSheet("no tracks".into()));
+    }
+
+    Ok(sheet)
+}
+
+impl CueSheet {
+    #[must_use]
+    pub fn entries(&self) -> Vec<CueEntry> {
+        self.tracks
+            .iter()
+            .enumerate()
+            .map(|(i, track)| {
+                let end = self
+                    .tracks
+                    .get(i + 1)
+                    .filter(|next| next.file == track.file)
+                    .map(|next| next.start);
+
+                CueEntry {
+                    file: track.file.clone(),
+                    number: track.number,
+                    title: track.title.clone(),
+                    performer: track.performer.clone().or_else(|| self.performer.clone()),
+                    album: self.title.clone(),
+                    start: track.start,
+                    end,
+                }
+            })
+            .collect()
+    }
+
+    /// The audio files this sheet splits up, which shouldn't also show up as tracks of
+    /// their own.
+    #[must_use]
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+
+        for track in &self.tracks {
+            if !files.contains(&track.file) {
+                files.push(track.file.clone());
+            }
+        }
+
+        files
+    }
+}
+
+impl CueEntry {
+    #[must_use]
+    pub fn range(&self) -> TrackRange {
+        TrackRange {
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or_default().to_string(),
+        None => value
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+fn parse_time(time: &str) -> Option<Duration> {
+    let mut parts = time.split(':').map(|part| part.parse::<u64>().ok());
+
+    let (Some(Some(minutes)), Some(Some(seconds)), Some(Some(frames)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+
+    Some(
+        Duration::from_secs(minutes * 60 + seconds)
+            + Duration::from_millis(frames * 1000 / FRAMES_PER_SECOND),
+    )
+}
+
+// Sheets often still name the WAV they were ripped to after it was re-encoded, so fall
+// back to any playable file with the same stem.
+fn resolve_file(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+
+    if path.exists() {
+        return path;
+    }
+
+    formats::extensions()
+        .into_iter()
+        .map(|ext| path.with_extension(ext))
+        .find(|candidate| candidate.exists())
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use uuid::Uuid;
+
+    fn fixture_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wiremann-cue-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn index_times_count_75_frames_a_second() {
+        assert_eq!(parse_time("00:00:00"), Some(Duration::ZERO));
+        assert_eq!(parse_time("03:02:00"), Some(Duration::from_secs(182)));
+        assert_eq!(parse_time("00:00:15"), Some(Duration::from_millis(200)));
+        // Frames that don't land on a whole millisecond are rounded down.
+        assert_eq!(
+            parse_time("01:00:74"),
+            Some(Duration::from_mins(1) + Duration::from_millis(986))
+        );
+
+        assert_eq!(parse_time("00:01"), None);
+        assert_eq!(parse_time("00:01:02:03"), None);
+        assert_eq!(parse_time("00:aa:00"), None);
+    }
+
+    #[test]
+    fn multi_file_sheet_ends_tracks_at_their_own_file() {
+        let sheet = parse(
+            r#"
+            PERFORMER "Band"
+            TITLE "Album"
+            FILE "one.flac" WAVE
+              TRACK 01 AUDIO
+                TITLE "First"
+                INDEX 01 00:00:00
+              TRACK 02 AUDIO
+                TITLE "Second"
+                PERFORMER "Guest"
+                INDEX 00 02:58:00
+                INDEX 01 03:02:00
+            FILE "two.flac" WAVE
+              TRACK 03 AUDIO
+                TITLE "Third"
+                INDEX 01 00:00:00
+            "#,
+            Path::new("/music"),
+        )
+        .unwrap();
+
+        let entries = sheet.entries();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].file, Path::new("/music/one.flac"));
+        assert_eq!(entries[0].start, Duration::ZERO);
+        assert_eq!(entries[0].end, Some(Duration::from_secs(182)));
+        assert_eq!(entries[0].performer.as_deref(), Some("Band"));
+        assert_eq!(entries[0].album.as_deref(), Some("Album"));
+
+        // The pregap from INDEX 00 stays with the track before.
+        assert_eq!(entries[1].start, Duration::from_secs(182));
+        assert_eq!(entries[1].end, None);
+        assert_eq!(entries[1].performer.as_deref(), Some("Guest"));
+
+        assert_eq!(entries[2].file, Path::new("/music/two.flac"));
+        assert_eq!(entries[2].number, 3);
+        assert_eq!(entries[2].title.as_deref(), Some("Third"));
+        assert_eq!(entries[2].start, Duration::ZERO);
+        assert_eq!(entries[2].end, None);
+
+        assert_eq!(
+            sheet.files(),
+            vec![
+                PathBuf::from("/music/one.flac"),
+                PathBuf::from("/music/two.flac")
+            ]
+        );
+    }
+
+    #[test]
+    fn track_before_file_is_rejected() {
+        let sheet = parse(
+            "TRACK 01 AUDIO\nINDEX 01 00:00:00\nFILE \"one.flac\" WAVE\n",
+            Path::new("/music"),
+        );
+
+        assert!(matches!(sheet, Err(ScannerError::CueSheet(_))));
+    }
+
+    #[test]
+    fn tracks_without_a_start_are_dropped() {
+        let sheet = parse(
+            "FILE \"one.flac\" WAVE\nTRACK 01 AUDIO\nINDEX 00 00:00:00\nTRACK 02 AUDIO\nINDEX 01 01:00:00\n",
+            Path::new("/music"),
+        )
+        .unwrap();
+
+        assert_eq!(sheet.tracks.len(), 1);
+        assert_eq!(sheet.tracks[0].number, 2);
+
+        let empty = parse(
+            "FILE \"one.flac\" WAVE\nTRACK 01 AUDIO\n",
+            Path::new("/music"),
+        );
+        assert!(matches!(empty, Err(ScannerError::CueSheet(_))));
+
+        let invalid = parse(
+            "FILE \"one.flac\" WAVE\nTRACK 01 AUDIO\nINDEX 01 1:00\n",
+            Path::new("/music"),
+        );
+        assert!(matches!(invalid, Err(ScannerError::CueSheet(_))));
+    }
+
+    #[test]
+    fn reads_latin1_and_bom_prefixed_sheets() {
+        let dir = fixture_dir();
+
+        let latin1 = dir.join("latin1.cue");
+        fs::write(
+            &latin1,
+            b"TITLE \"Caf\xe9\"\nFILE \"a.flac\" WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\n",
+        )
+        .unwrap();
+
+        let bom = dir.join("bom.cue");
+        fs::write(
+            &bom,
+            "\u{feff}TITLE \"Caf\u{e9}\"\nFILE \"a.flac\" WAVE\nTRACK 01 AUDIO\nINDEX 01 00:00:00\n",
+        )
+        .unwrap();
+
+        assert_eq!(read(&latin1).unwrap().title.as_deref(), Some("Caf\u{e9}"));
+        assert_eq!(read(&bom).unwrap().title.as_deref(), Some("Caf\u{e9}"));
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn files_fall_back_to_a_re_encoded_copy() {
+        let dir = fixture_dir();
+        fs::write(dir.join("album.flac"), b"").unwrap();
+
+        assert_eq!(resolve_file(&dir, "album.wav"), dir.join("album.flac"));
+        assert_eq!(resolve_file(&dir, "other.wav"), dir.join("other.wav"));
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,134 @@
+use crate::audio::formats;
+use crate::audio::span::Span;
+use crate::controller::state::{ReplayGain, TrackSource};
+use crate::errors::ScannerError;
+use rodio::Source;
+use std::f64::consts::PI;
+use std::fs::File;
+
+const REFERENCE_LUFS: f64 = -18.0;
+const ABSOLUTE_GATE: f64 = -70.0;
+const RELATIVE_GATE: f64 = -10.0;
+
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 3],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[1] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[2] * y;
+        y
+    }
+}
+
+fn k_weighting(rate: f64) -> (Biquad, Biquad) {
+    let f0 = 1681.974_450_955_533;
+    let g = 3.999_843_853_973_347;
+    let q = 0.707_175_236_955_419_6;
+
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(g / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    let f0 = 38.135_470_876_024_44;
+    let q = 0.500_327_037_323_877_3;
+
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    (shelf, high_pass)
+}
+
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+#[allow(clippy::missing_errors_doc)]
+pub fn analyze(source: &TrackSource) -> Result<ReplayGain, ScannerError> {
+    let path = source.path().ok_or(ScannerError::StreamSource)?;
+    let decoder = formats::decoder_builder::<File>(path)?
+        .with_seekable(true)
+        .build()?;
+    let decoder = Span::new(decoder, source.range)?;
+
+    let channels = usize::from(decoder.channels().get());
+    let rate = f64::from(decoder.sample_rate().get());
+
+    let mut filters: Vec<(Biquad, Biquad)> = (0..channels).map(|_| k_weighting(rate)).collect();
+
+    // Power is collected in 100ms segments, four of which make one 400ms gating block.
+    let segment_len = (rate / 10.0) as usize * channels;
+    let mut segments: Vec<f64> = Vec::new();
+    let mut segment_sum = 0.0;
+    let mut segment_count = 0;
+
+    let mut peak = 0f32;
+
+    for (i, sample) in decoder.enumerate() {
+        peak = peak.max(sample.abs());
+
+        let (shelf, high_pass) = &mut filters[i % channels];
+        let weighted = high_pass.process(shelf.process(f64::from(sample)));
+
+        segment_sum += weighted * weighted;
+        segment_count += 1;
+
+        if segment_count == segment_len {
+            segments.push(segment_sum / (rate / 10.0));
+            segment_sum = 0.0;
+            segment_count = 0;
+        }
+    }
+
+    let blocks: Vec<f64> = segments
+        .windows(4)
+        .map(|window| window.iter().sum::<f64>() / 4.0)
+        .filter(|power| loudness(*power) > ABSOLUTE_GATE)
+        .collect();
+
+    if blocks.is_empty() {
+        return Ok(ReplayGain::default());
+    }
+
+    let mean = blocks.iter().sum::<f64>() / blocks.len() as f64;
+    let threshold = loudness(mean) + RELATIVE_GATE;
+
+    let gated: Vec<f64> = blocks
+        .into_iter()
+        .filter(|power| loudness(*power) > threshold)
+        .collect();
+
+    let integrated = if gated.is_empty() {
+        loudness(mean)
+    } else {
+        loudness(gated.iter().sum::<f64>() / gated.len() as f64)
+    };
+
+    Ok(ReplayGain {
+        track_gain: Some((REFERENCE_LUFS - integrated) as f32),
+        track_peak: Some(peak),
+        album_gain: None,
+        album_peak: None,
+    })
+}
@@ -1,19 +1,28 @@
-use crate::controller::state::{Track, TrackId, TrackSource};
+use super::chapters;
+use super::cue::CueEntry;
+use super::station::Station;
+use crate::controller::state::{
+    AudioProperties, ReplayGain, Track, TrackId, TrackSource, TrackTags,
+};
 use crate::errors::ScannerError;
-use lofty::file::{AudioFile, TaggedFileExt};
+use lofty::file::{AudioFile, FileType, TaggedFile, TaggedFileExt};
 use lofty::read_from_path;
-use lofty::tag::ItemKey;
+use lofty::tag::{ItemKey, Tag};
 use std::path::Path;
 use std::time::Duration;
 
 #[allow(clippy::missing_errors_doc)]
 pub fn read_metadata(track_source: TrackSource) -> Result<Track, ScannerError> {
-    let path = track_source.path.as_path();
+    let path = track_source.path().ok_or(ScannerError::StreamSource)?;
 
     let file = read_from_path(path).ok();
 
     let (mut title, mut artist, mut album) = fallback_metadata(path);
     let mut duration = Duration::from_millis(0);
+    let mut tags = TrackTags::default();
+    let mut properties = AudioProperties::default();
+    let mut replay_gain = ReplayGain::default();
+    let mut chapters = Vec::new();
 
     if let Some(tagged_file) = file {
         if let Some(tag) = tagged_file
@@ -40,9 +49,14 @@ pub fn read_metadata(track_source: TrackSource) -> Result<Track, ScannerError> {
             if let Some(a) = tag.get_string(ItemKey::AlbumTitle) {
                 album = a.to_string();
             }
+
+            tags = read_tags(tag);
+            replay_gain = read_replay_gain(tag);
         }
 
         duration = tagged_file.properties().duration();
+        properties = read_properties(&tagged_file);
+        chapters = chapters::read(path, tagged_file.file_type());
     }
 
     let track_id = TrackId::generate(&title, &artist, &album)?;
@@ -54,10 +68,187 @@ pub fn read_metadata(track_source: TrackSource) -> Result<Track, ScannerError> {
         artist,
         album,
         duration,
+        tags,
+        properties,
+        replay_gain,
+        chapters,
+        image_id: None,
+    })
+}
+
+/// Builds the track for one CUE sheet entry, using the file's own tags where the sheet
+/// leaves something out.
+#[allow(clippy::missing_errors_doc)]
+pub fn read_cue_metadata(
+    track_source: TrackSource,
+    entry: &CueEntry,
+) -> Result<Track, ScannerError> {
+    let file = read_metadata(track_source.clone())?;
+
+    let title = entry
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("Track {:02}", entry.number));
+    let artist = entry.performer.clone().unwrap_or(file.artist);
+    let album = entry.album.clone().unwrap_or(file.album);
+
+    let end = entry.end.unwrap_or(file.duration);
+
+    // The file's track gain covers the whole album, so only its album gain still applies.
+    let replay_gain = ReplayGain {
+        track_gain: None,
+        track_peak: None,
+        ..file.replay_gain
+    };
+
+    // Album-wide tags still hold for each entry, but not the ones about the file's one track.
+    let tags = TrackTags {
+        track_number: Some(entry.number),
+        bpm: None,
+        comment: None,
+        ..file.tags
+    };
+
+    let track_id = TrackId::generate(&title, &artist, &album)?;
+
+    Ok(Track {
+        sources: vec![track_source],
+        id: track_id,
+        title,
+        artist,
+        album,
+        duration: end.saturating_sub(entry.start),
+        tags,
+        properties: file.properties,
+        replay_gain,
+        chapters: Vec::new(),
         image_id: None,
     })
 }
 
+/// Builds the track for a radio station. Streams have no tags to read up front, so it
+/// goes by the station file's title, or the host when there is none.
+#[allow(clippy::missing_errors_doc)]
+pub fn read_station_metadata(
+    track_source: TrackSource,
+    station: &Station,
+) -> Result<Track, ScannerError> {
+    let host = station
+        .url
+        .split_once("://")
+        .map_or(station.url.as_str(), |(_, rest)| rest)
+        .split(['/', '?'])
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let title = station.title.clone().unwrap_or_else(|| host.clone());
+    let album = "Internet Radio".to_string();
+
+    let track_id = TrackId::generate(&title, &host, &album)?;
+
+    Ok(Track {
+        sources: vec![track_source],
+        id: track_id,
+        title,
+        artist: host,
+        album,
+        duration: Duration::ZERO,
+        tags: TrackTags::default(),
+        properties: AudioProperties::default(),
+        replay_gain: ReplayGain::default(),
+        chapters: Vec::new(),
+        image_id: None,
+    })
+}
+
+fn read_tags(tag: &Tag) -> TrackTags {
+    let text = |key: ItemKey| {
+        tag.get_string(key)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+    };
+
+    // Numbers are often tagged with their total, like "3/12".
+    let number = |key: ItemKey| {
+        text(key).and_then(|value| value.split('/').next()?.trim().parse::<u32>().ok())
+    };
+
+    let date = text(ItemKey::RecordingDate).or_else(|| text(ItemKey::Year));
+    let year = date
+        .as_deref()
+        .and_then(|date| date.get(..4))
+        .and_then(|year| year.parse::<u32>().ok());
+
+    TrackTags {
+        album_artist: text(ItemKey::AlbumArtist),
+        track_number: number(ItemKey::TrackNumber),
+        disc_number: number(ItemKey::DiscNumber),
+        year,
+        date,
+        genre: text(ItemKey::Genre),
+        composer: text(ItemKey::Composer),
+        bpm: text(ItemKey::Bpm)
+            .or_else(|| text(ItemKey::IntegerBpm))
+            .and_then(|bpm| bpm.parse::<f32>().ok()),
+        comment: text(ItemKey::Comment),
+    }
+}
+
+fn read_properties(file: &TaggedFile) -> AudioProperties {
+    let properties = file.properties();
+
+    AudioProperties {
+        bitrate: properties
+            .audio_bitrate()
+            .or_else(|| properties.overall_bitrate()),
+        sample_rate: properties.sample_rate(),
+        channels: properties.channels(),
+        codec: codec_name(file.file_type()).map(ToString::to_string),
+    }
+}
+
+fn codec_name(file_type: FileType) -> Option<&'static str> {
+    match file_type {
+        FileType::Aac => Some("AAC"),
+        FileType::Aiff => Some("AIFF"),
+        FileType::Ape => Some("APE"),
+        FileType::Flac => Some("FLAC"),
+        FileType::Mpeg => Some("MP3"),
+        FileType::Mp4 => Some("MP4"),
+        FileType::Mpc => Some("Musepack"),
+        FileType::Opus => Some("Opus"),
+        FileType::Vorbis => Some("Vorbis"),
+        FileType::Speex => Some("Speex"),
+        FileType::Wav => Some("WAV"),
+        FileType::WavPack => Some("WavPack"),
+        _ => None,
+    }
+}
+
+fn read_replay_gain(tag: &Tag) -> ReplayGain {
+    let parse = |key: ItemKey| {
+        tag.get_string(key).and_then(|value| {
+            let value = value.trim();
+            let value = value
+                .strip_suffix("dB")
+                .or_else(|| value.strip_suffix("db"))
+                .or_else(|| value.strip_suffix("DB"))
+                .unwrap_or(value);
+
+            value.trim().parse::<f32>().ok()
+        })
+    };
+
+    ReplayGain {
+        track_gain: parse(ItemKey::ReplayGainTrackGain),
+        track_peak: parse(ItemKey::ReplayGainTrackPeak),
+        album_gain: parse(ItemKey::ReplayGainAlbumGain),
+        album_peak: parse(ItemKey::ReplayGainAlbumPeak),
+    }
+}
+
 #[allow(clippy::missing_errors_doc)]
 pub fn read_album_art(path: &Path) -> Result<Option<Box<[u8]>>, ScannerError> {
     let file = read_from_path(path).ok();
@@ -1,23 +1,36 @@
+pub mod chapters;
+pub mod cue;
+pub mod loudness;
 pub mod metadata;
-use crate::app::AppPaths;
-use crate::cacher::CachedTrackSource;
+pub mod station;
+pub mod watcher;
+pub mod waveform;
+use crate::app::{AppPaths, spawn_pool_worker};
+use crate::audio::formats;
+use crate::cacher::io::{LOUDNESS_FAILURES_VERSION, SCAN_RECORD_VERSION, read_cache, write_cache};
+use crate::cacher::legacy::LegacyScanRecord;
+use crate::cacher::migrate::read_migrated;
+use crate::cacher::{CachedLoudnessFailures, CachedScanEntry, CachedScanRecord};
+use crate::controller::events::RescanSummary;
 use crate::controller::state::{Playlist, PlaylistId, PlaylistSource};
-use crate::controller::state::{Track, TrackSource};
+use crate::controller::state::{SourceLocation, Track, TrackRange, TrackSource};
 use crate::{
     controller::{commands::ScannerCommand, events::ScannerEvent, state::TrackId},
     errors::ScannerError,
 };
 use crossbeam_channel::{Receiver, Sender, select, tick};
-use dashmap::DashMap;
+use cue::CueEntry;
+use dashmap::{DashMap, DashSet};
+use station::Station;
 use std::cmp::PartialEq;
-use std::collections::{HashMap, VecDeque};
-use std::ffi::OsStr;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Duration;
 use uuid::Uuid;
 use walkdir::WalkDir;
+use watcher::{Change, FolderWatcher};
 
 pub struct Scanner {
     pub tx: Sender<ScannerEvent>,
@@ -30,6 +43,15 @@ pub struct Scanner {
 
     scan_progress: Arc<ScanProgress>,
     scan_record: ScanRecord,
+
+    watcher: Option<FolderWatcher>,
+    rescanning: Arc<AtomicBool>,
+
+    analyzing: Arc<DashSet<TrackId>>,
+    summarizing: Arc<DashSet<TrackId>>,
+    // Sources loudness analysis has already failed on, so they aren't decoded again on
+    // every play.
+    loudness_failures: HashSet<TrackSource>,
 }
 
 #[derive(PartialEq)]
@@ -46,6 +68,23 @@ struct ScanProgress {
 
 type ScanRecord = Arc<DashMap<TrackSource, TrackId>>;
 
+// Changes under the watched folders, each with the folder playlist it falls under.
+type ChangeBatch = Vec<(Change, Option<PlaylistId>)>;
+
+// Where a source came from, for the ones whose own tags don't tell the whole story.
+enum Origin {
+    File,
+    Cue(CueEntry),
+    Station(Station),
+}
+
+// Work for the worker pool, which reads files for scans and analyses them on request.
+enum Job {
+    Scan(PathBuf, Option<PlaylistId>),
+    Loudness(TrackId, TrackSource),
+    Waveform(TrackId, TrackSource),
+}
+
 impl Scanner {
     #[must_use]
     pub fn new(app_paths: AppPaths) -> (Self, Sender<ScannerCommand>, Receiver<ScannerEvent>) {
@@ -67,6 +106,13 @@ impl Scanner {
                 processed: AtomicUsize::new(0),
             }),
             scan_record: Arc::new(DashMap::new()),
+
+            watcher: None,
+            rescanning: Arc::new(AtomicBool::new(false)),
+
+            analyzing: Arc::new(DashSet::new()),
+            summarizing: Arc::new(DashSet::new()),
+            loudness_failures: HashSet::new(),
         };
 
         (scanner, cmd_tx, event_rx)
@@ -75,72 +121,326 @@ impl Scanner {
     #[allow(clippy::missing_errors_doc)]
     pub fn run(&mut self, metadata_workers: usize) -> Result<(), ScannerError> {
         let (worker_tx, worker_rx) = crossbeam_channel::bounded(64);
+        let (failed_tx, failed_rx) = crossbeam_channel::unbounded();
+        let (crashed_tx, crashed_rx) = crossbeam_channel::unbounded();
+
+        self.spawn_metadata_workers(&worker_rx, &failed_tx, &crashed_tx, metadata_workers);
+        self.read_loudness_failures();
+
+        let (fs_tx, fs_rx) = crossbeam_channel::unbounded();
+        let (change_tx, change_rx) = crossbeam_channel::unbounded();
+        let settle = tick(Duration::from_millis(500));
 
-        self.spawn_metadata_workers(&worker_rx, metadata_workers);
+        self.spawn_change_worker(change_rx, &crashed_tx);
+
+        self.watcher = FolderWatcher::new(fs_tx)
+            .inspect_err(|e| tracing::warn!(error = %e, "Folder watching is unavailable"))
+            .ok();
 
         loop {
-            match self.rx.recv()? {
-                ScannerCommand::ScanDir(path) => {
-                    self.queue.push_back(path);
+            select! {
+                recv(self.rx) -> cmd => match cmd? {
+                    ScannerCommand::ScanDir(path) => {
+                        self.queue.push_back(path);
+
+                        if self.state == State::Idle
+                            && let Some(path) = self.queue.pop_front()
+                        {
+                            self.state = State::Scanning;
+                            self.scan_folder(path, &worker_tx);
+                        }
+                    }
+                    ScannerCommand::StartNextScan => {
+                        self.state = State::Idle;
+                        self.write_scan_record();
+
+                        if self.state == State::Idle
+                            && let Some(path) = self.queue.pop_front()
+                        {
+                            self.state = State::Scanning;
+                            self.scan_folder(path, &worker_tx);
+                        }
+                    }
+                    ScannerCommand::ScanTrack(path) => {
+                        if formats::is_playable(&path) || station::is_station_file(&path) {
+                            worker_tx.send(Job::Scan(path, None)).ok();
+                        } else {
+                            tracing::warn!(path = %path.display(), "Skipping unsupported audio file");
+                        }
+                    }
+                    ScannerCommand::WatchFolder(playlist, root) => {
+                        // Nothing has been scanned yet this session to load the record.
+                        if self.scan_record.is_empty() {
+                            self.read_scan_record();
+                        }
 
-                    if self.state == State::Idle
-                        && let Some(path) = self.queue.pop_front()
+                        self.watch_folder(playlist, root);
+                    }
+                    ScannerCommand::Rescan(roots) => self.rescan(roots),
+                    ScannerCommand::AnalyzeLoudness(id, source) => {
+                        self.analyze_loudness(id, source, &worker_tx);
+                    }
+                    ScannerCommand::AnalyzeWaveform(id, source) => {
+                        self.analyze_waveform(id, source, &worker_tx);
+                    }
+                },
+                recv(crashed_rx) -> message => {
+                    return Err(ScannerError::WorkerPanicked(message?));
+                }
+                recv(failed_rx) -> source => {
+                    if let Ok(source) = source {
+                        self.loudness_failures.insert(source);
+                        self.write_loudness_failures();
+                    }
+                }
+                recv(fs_rx) -> event => {
+                    if let Ok(Ok(event)) = event
+                        && let Some(watcher) = self.watcher.as_mut()
                     {
-                        self.state = State::Scanning;
-                        self.scan_folder(path, &worker_tx);
+                        watcher.handle(event);
+                    }
+                }
+                recv(settle) -> _ => {
+                    if let Some(watcher) = self.watcher.as_mut() {
+                        let batch: ChangeBatch = watcher
+                            .settled()
+                            .into_iter()
+                            .map(|change| {
+                                let playlist = match &change {
+                                    Change::Path(path) | Change::Renamed { to: path, .. } => {
+                                        watcher.playlist_for(path)
+                                    }
+                                };
+
+                                (change, playlist)
+                            })
+                            .collect();
+
+                        if !batch.is_empty() {
+                            change_tx.send(batch).ok();
+                        }
                     }
                 }
-                ScannerCommand::StartNextScan => {
-                    self.state = State::Idle;
-                    self.write_scan_record();
+            }
+        }
+    }
+
+    fn watch_folder(&mut self, playlist: PlaylistId, root: PathBuf) {
+        let Some(watcher) = self.watcher.as_mut() else {
+            return;
+        };
+
+        if let Err(e) = watcher.watch(root.clone(), playlist) {
+            tracing::warn!(path = %root.display(), error = %e, "Couldn't watch folder");
+        }
+    }
+
+    fn rescan(&self, roots: Vec<(PlaylistId, PathBuf)>) {
+        if self.rescanning.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        if self.scan_record.is_empty() {
+            self.read_scan_record();
+        }
+
+        let tx = self.tx.clone();
+        let scan_record = self.scan_record.clone();
+        let cache_dir = self.app_paths.cache.clone();
+        let rescanning = self.rescanning.clone();
+
+        std::thread::spawn(move || {
+            let summary = Self::rescan_roots(&roots, &scan_record, &tx);
+
+            Self::save_scan_record(&cache_dir, &scan_record);
+            tx.send(ScannerEvent::RescanFinished(summary)).ok();
+
+            rescanning.store(false, Ordering::Release);
+        });
+    }
+
+    // Compares what's under each root against the scan record: new and changed files get
+    // read, and sources that are gone get dropped along with tracks left without any.
+    fn rescan_roots(
+        roots: &[(PlaylistId, PathBuf)],
+        scan_record: &ScanRecord,
+        tx: &Sender<ScannerEvent>,
+    ) -> RescanSummary {
+        let mut summary = RescanSummary::default();
+        let mut new = Vec::new();
+        let mut lost: HashSet<TrackId> = HashSet::new();
+
+        let mut known: HashMap<(SourceLocation, Option<TrackRange>), (TrackSource, TrackId)> =
+            scan_record
+                .iter()
+                .filter(|entry| {
+                    entry
+                        .key()
+                        .path()
+                        .is_some_and(|path| roots.iter().any(|(_, root)| path.starts_with(root)))
+                })
+                .map(|entry| {
+                    let source = entry.key().clone();
+                    (
+                        (source.location.clone(), source.range),
+                        (source, *entry.value()),
+                    )
+                })
+                .collect();
+
+        let mut missing: Vec<(TrackSource, TrackId)> = known
+            .values()
+            .filter(|(source, _)| source.content_hash.is_some() && !source.is_available())
+            .cloned()
+            .collect();
+
+        for (playlist, root) in roots {
+            for file in discover_files(root, |_| {}) {
+                let Ok(sources) = Self::job_sources(&file) else {
+                    continue;
+                };
+
+                for (ts, origin) in sources {
+                    let previous = known.remove(&(ts.location.clone(), ts.range));
+
+                    if scan_record.contains_key(&ts) {
+                        continue;
+                    }
+
+                    let ts = ts.with_content_hash();
 
-                    if self.state == State::Idle
-                        && let Some(path) = self.queue.pop_front()
+                    if previous.is_none()
+                        && let Some(moved) =
+                            missing.iter().position(|(old, _)| old.same_content(&ts))
                     {
-                        self.state = State::Scanning;
-                        self.scan_folder(path, &worker_tx);
+                        let (old, id) = missing.swap_remove(moved);
+                        known.remove(&(old.location.clone(), old.range));
+
+                        Self::replace_source(&old, ts, id, scan_record, tx);
+                        summary.updated += 1;
+                        continue;
+                    }
+
+                    if let Some((old, id)) = &previous {
+                        scan_record.remove(old);
+                        lost.insert(*id);
+
+                        tx.send(ScannerEvent::RemoveTrackSource(*id, file.clone()))
+                            .ok();
+                    }
+
+                    if let Ok(track) = Self::read_track(ts.clone(), &origin) {
+                        scan_record.insert(ts, track.id);
+                        new.push((track, Some(*playlist)));
+
+                        if previous.is_some() {
+                            summary.updated += 1;
+                        } else {
+                            summary.added += 1;
+                        }
                     }
-                }
-                ScannerCommand::ScanTrack(path) => {
-                    worker_tx.send((path, None)).ok();
                 }
             }
         }
+
+        // Whatever wasn't found again is gone from disk.
+        lost.extend(known.values().map(|(_, id)| *id));
+        Self::drop_sources(known.into_values(), scan_record, tx);
+
+        if !new.is_empty() {
+            tx.send(ScannerEvent::UpsertTracks(new)).ok();
+        }
+
+        let remaining = recorded_tracks(scan_record);
+
+        summary.removed = lost
+            .into_iter()
+            .filter(|id| !remaining.contains(id))
+            .collect();
+
+        summary
+    }
+
+    fn analyze_loudness(&self, id: TrackId, source: TrackSource, worker_tx: &Sender<Job>) {
+        if self.loudness_failures.contains(&source) || !self.analyzing.insert(id) {
+            return;
+        }
+
+        worker_tx.send(Job::Loudness(id, source)).ok();
+    }
+
+    fn analyze_waveform(&self, id: TrackId, source: TrackSource, worker_tx: &Sender<Job>) {
+        if !self.summarizing.insert(id) {
+            return;
+        }
+
+        worker_tx.send(Job::Waveform(id, source)).ok();
     }
 
     fn spawn_metadata_workers(
         &self,
-        worker_rx: &Receiver<(PathBuf, Option<PlaylistId>)>,
+        worker_rx: &Receiver<Job>,
+        failed_tx: &Sender<TrackSource>,
+        crashed: &Sender<String>,
         workers: usize,
     ) {
         let ticker = tick(Duration::from_millis(128));
 
         for _ in 0..workers {
             let worker_rx = worker_rx.clone();
+            let failed_tx = failed_tx.clone();
             let scan_progress = self.scan_progress.clone();
             let tx = self.tx.clone();
             let scan_record = self.scan_record.clone();
+            let analyzing = self.analyzing.clone();
+            let summarizing = self.summarizing.clone();
             let ticker = ticker.clone();
 
-            std::thread::spawn(move || {
+            spawn_pool_worker("scanner metadata", crashed, move || {
                 let mut new: Vec<(Track, Option<PlaylistId>)> = Vec::with_capacity(32);
                 let mut existing: HashMap<PlaylistId, Vec<TrackId>> = HashMap::with_capacity(32);
 
                 loop {
                     select! {
-                        recv(worker_rx) -> job => {
-                            if let Ok((path, pid)) = job {
-                                Self::handle_job(
-                                    path.as_path(),
-                                    pid,
-                                    &scan_record,
-                                    &scan_progress,
-                                    &tx,
-                                    &mut existing,
-                                    &mut new,
-                                );
+                        recv(worker_rx) -> job => match job {
+                            Ok(Job::Scan(path, pid)) => Self::handle_job(
+                                path.as_path(),
+                                pid,
+                                &scan_record,
+                                &scan_progress,
+                                &tx,
+                                &mut existing,
+                                &mut new,
+                            ),
+                            Ok(Job::Loudness(id, source)) => {
+                                match loudness::analyze(&source) {
+                                    Ok(replay_gain) => {
+                                        tx.send(ScannerEvent::LoudnessAnalyzed(id, replay_gain))
+                                            .ok();
+                                    }
+                                    Err(e) => {
+                                        tracing::warn!(error = %e, "Loudness analysis failed");
+                                        failed_tx.send(source).ok();
+                                    }
+                                }
+
+                                analyzing.remove(&id);
                             }
-                        }
+                            Ok(Job::Waveform(id, source)) => {
+                                if let Ok(waveform) = waveform::summarize(&source) {
+                                    tx.send(ScannerEvent::WaveformAnalyzed(id, Arc::new(waveform)))
+                                        .ok();
+                                }
+
+                                summarizing.remove(&id);
+                            }
+                            // The engine is gone, e.g. crashed and being restarted with
+                            // a fresh pool.
+                            Err(_) => {
+                                Self::flush_batches(&tx, &mut existing, &mut new);
+                                break;
+                            }
+                        },
 
                         recv(ticker) -> _ => {
                             Self::flush_batches(&tx, &mut existing, &mut new);
@@ -162,40 +462,46 @@ impl Scanner {
     ) {
         let mut incremented = false;
 
-        let Ok(ts) = TrackSource::generate(path) else {
+        let Ok(sources) = Self::job_sources(path) else {
             scan_progress.processed.fetch_add(1, Ordering::Relaxed);
             return;
         };
 
-        if let Some(entry) = scan_record.get(&ts) {
-            if let Some(pid) = pid {
-                let batch = existing.entry(pid).or_default();
-                batch.push(*entry.value());
+        for (ts, origin) in sources {
+            if let Some(known) = scan_record.get(&ts) {
+                if let Some(pid) = pid {
+                    let batch = existing.entry(pid).or_default();
+                    batch.push(*known.value());
 
-                if batch.len() >= 32 {
-                    let to_send = std::mem::take(batch);
-                    tx.send(ScannerEvent::InsertTracksIntoPlaylist(pid, to_send))
-                        .ok();
+                    if batch.len() >= 32 {
+                        let to_send = std::mem::take(batch);
+                        tx.send(ScannerEvent::InsertTracksIntoPlaylist(pid, to_send))
+                            .ok();
+                    }
+
+                    incremented = true;
                 }
+            } else {
+                let ts = ts.with_content_hash();
 
-                scan_progress.processed.fetch_add(1, Ordering::Relaxed);
-                incremented = true;
-            }
-        } else {
-            if let Ok(track) = metadata::read_metadata(ts.clone()) {
-                let id = track.id;
-                new.push((track, pid));
+                if let Ok(track) = Self::read_track(ts.clone(), &origin) {
+                    let id = track.id;
+                    new.push((track, pid));
+
+                    if new.len() >= 32 {
+                        let to_send = std::mem::take(new);
+                        tx.send(ScannerEvent::UpsertTracks(to_send)).ok();
+                    }
 
-                if new.len() >= 32 {
-                    let to_send = std::mem::take(new);
-                    tx.send(ScannerEvent::UpsertTracks(to_send)).ok();
+                    scan_record.insert(ts, id);
                 }
 
-                scan_record.insert(ts, id);
+                incremented = true;
             }
+        }
 
+        if incremented {
             scan_progress.processed.fetch_add(1, Ordering::Relaxed);
-            incremented = true;
         }
 
         let processed = scan_progress.processed.load(Ordering::Relaxed);
@@ -209,6 +515,38 @@ impl Scanner {
         }
     }
 
+    fn read_track(ts: TrackSource, origin: &Origin) -> Result<Track, ScannerError> {
+        match origin {
+            Origin::File => metadata::read_metadata(ts),
+            Origin::Cue(entry) => metadata::read_cue_metadata(ts, entry),
+            Origin::Station(station) => metadata::read_station_metadata(ts, station),
+        }
+    }
+
+    // A CUE sheet stands for one source per entry, all pointing into the file it splits,
+    // and a station file for one per stream it lists.
+    fn job_sources(path: &Path) -> Result<Vec<(TrackSource, Origin)>, ScannerError> {
+        if station::is_station_file(path) {
+            return Ok(station::read(path)?
+                .into_iter()
+                .map(|station| (TrackSource::stream(&station.url), Origin::Station(station)))
+                .collect());
+        }
+
+        if !cue::is_cue_sheet(path) {
+            return Ok(vec![(TrackSource::generate(path)?, Origin::File)]);
+        }
+
+        cue::read(path)?
+            .entries()
+            .into_iter()
+            .map(|entry| {
+                let source = TrackSource::generate(&entry.file)?.with_range(entry.range());
+                Ok((source, Origin::Cue(entry)))
+            })
+            .collect()
+    }
+
     fn flush_batches(
         tx: &Sender<ScannerEvent>,
         existing: &mut HashMap<PlaylistId, Vec<TrackId>>,
@@ -230,7 +568,7 @@ impl Scanner {
         }
     }
 
-    fn scan_folder(&self, path: PathBuf, worker_tx: &Sender<(PathBuf, Option<PlaylistId>)>) {
+    fn scan_folder(&mut self, path: PathBuf, worker_tx: &Sender<Job>) {
         self.scan_progress.total.store(0, Ordering::Relaxed);
         self.scan_progress.processed.store(0, Ordering::Relaxed);
         self.scan_progress
@@ -241,8 +579,6 @@ impl Scanner {
 
         self.tx.send(ScannerEvent::ScanStarted).ok();
 
-        let exts = ["mp3", "wav", "ogg", "aac", "m4a"];
-
         if path.is_dir() {
             let playlist_id = PlaylistId(Uuid::new_v4());
 
@@ -262,28 +598,18 @@ impl Scanner {
 
             let _ = self.tx.send(ScannerEvent::InsertPlaylist(playlist));
 
+            self.watch_folder(playlist_id, path.clone());
+
             let scan_progress = self.scan_progress.clone();
             let worker_tx = worker_tx.clone();
             let tx = self.tx.clone();
 
             std::thread::spawn(move || {
-                let mut paths = Vec::with_capacity(1024);
-
-                for entry in WalkDir::new(&path)
-                    .into_iter()
-                    .filter_map(Result::ok)
-                    .filter(|e| {
-                        e.path()
-                            .extension()
-                            .and_then(OsStr::to_str)
-                            .is_some_and(|ext| exts.contains(&ext))
-                    })
-                {
-                    if paths.len() % 16 == 0 {
-                        tx.send(ScannerEvent::Discovered(paths.len())).ok();
+                let paths = discover_files(&path, |found| {
+                    if found % 16 == 0 {
+                        tx.send(ScannerEvent::Discovered(found)).ok();
                     }
-                    paths.push(entry.path().to_path_buf());
-                }
+                });
 
                 let total = paths.len();
                 scan_progress.total.store(total, Ordering::Relaxed);
@@ -291,7 +617,7 @@ impl Scanner {
                 scan_progress.discovery_done.store(true, Ordering::Release);
 
                 for path in paths {
-                    let _ = worker_tx.send((path, Some(playlist_id)));
+                    let _ = worker_tx.send(Job::Scan(path, Some(playlist_id)));
                 }
             });
         }
@@ -301,36 +627,386 @@ impl Scanner {
             .store(true, Ordering::Release);
     }
 
+    fn spawn_change_worker(&self, rx: Receiver<ChangeBatch>, crashed: &Sender<String>) {
+        let tx = self.tx.clone();
+        let scan_record = self.scan_record.clone();
+        let cache_dir = self.app_paths.cache.clone();
+
+        spawn_pool_worker("scanner changes", crashed, move || {
+            while let Ok(batch) = rx.recv() {
+                let before = recorded_tracks(&scan_record);
+                let mut new = Vec::new();
+                let mut missing = Self::missing_sources(&scan_record, &batch);
+
+                for (change, playlist) in batch {
+                    let mut refresh = |path: &Path, playlist| {
+                        Self::refresh_path(
+                            path,
+                            playlist,
+                            &scan_record,
+                            &tx,
+                            &mut missing,
+                            &mut new,
+                        );
+                    };
+
+                    match change {
+                        Change::Renamed { from, to } => {
+                            if !Self::move_sources(&from, &to, &scan_record, &tx) {
+                                refresh(&from, None);
+                                refresh(&to, playlist);
+                            }
+                        }
+                        Change::Path(path) => refresh(&path, playlist),
+                    }
+                }
+
+                Self::drop_sources(missing, &scan_record, &tx);
+
+                if !new.is_empty() {
+                    tx.send(ScannerEvent::UpsertTracks(new)).ok();
+                }
+
+                let after = recorded_tracks(&scan_record);
+                let removed: Vec<TrackId> = before.difference(&after).copied().collect();
+
+                if !removed.is_empty() {
+                    tx.send(ScannerEvent::RemoveTracks(removed)).ok();
+                }
+
+                Self::save_scan_record(&cache_dir, &scan_record);
+            }
+        });
+    }
+
+    // Whatever was known at or under `from` keeps its tracks and only swaps its sources,
+    // without reading the files again.
+    fn move_sources(
+        from: &Path,
+        to: &Path,
+        scan_record: &ScanRecord,
+        tx: &Sender<ScannerEvent>,
+    ) -> bool {
+        let moved: Vec<(TrackSource, TrackId)> = scan_record
+            .iter()
+            .filter(|entry| {
+                entry
+                    .key()
+                    .path()
+                    .is_some_and(|path| path.starts_with(from))
+            })
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+
+        if moved.is_empty() {
+            return false;
+        }
+
+        for (old, id) in moved {
+            let Some(rest) = old.path().and_then(|path| path.strip_prefix(from).ok()) else {
+                continue;
+            };
+
+            let path = if rest.as_os_str().is_empty() {
+                to.to_path_buf()
+            } else {
+                to.join(rest)
+            };
+
+            let Ok(mut source) = TrackSource::generate(&path) else {
+                continue;
+            };
+            source.range = old.range;
+            source.content_hash = old.content_hash;
+
+            Self::replace_source(&old, source, id, scan_record, tx);
+        }
+
+        true
+    }
+
+    // Hands the track behind `old` over to `source`, for a file that moved.
+    fn replace_source(
+        old: &TrackSource,
+        source: TrackSource,
+        id: TrackId,
+        scan_record: &ScanRecord,
+        tx: &Sender<ScannerEvent>,
+    ) {
+        scan_record.remove(old);
+        scan_record.insert(source.clone(), id);
+
+        if let Some(old_path) = old.path() {
+            tx.send(ScannerEvent::RemoveTrackSource(id, old_path.to_path_buf()))
+                .ok();
+        }
+        tx.send(ScannerEvent::AddTrackSource(id, source)).ok();
+    }
+
+    fn drop_sources(
+        sources: impl IntoIterator<Item = (TrackSource, TrackId)>,
+        scan_record: &ScanRecord,
+        tx: &Sender<ScannerEvent>,
+    ) {
+        for (source, id) in sources {
+            scan_record.remove(&source);
+
+            if let Some(path) = source.path() {
+                tx.send(ScannerEvent::RemoveTrackSource(id, path.to_path_buf()))
+                    .ok();
+            }
+        }
+    }
+
+    // Recorded sources under the changed paths whose files are gone, held back until the
+    // whole batch is seen in case one of them turns up again under another name.
+    fn missing_sources(
+        scan_record: &ScanRecord,
+        batch: &ChangeBatch,
+    ) -> Vec<(TrackSource, TrackId)> {
+        let paths: Vec<&Path> = batch
+            .iter()
+            .map(|(change, _)| match change {
+                Change::Path(path) | Change::Renamed { from: path, .. } => path.as_path(),
+            })
+            .collect();
+
+        scan_record
+            .iter()
+            .filter(|entry| {
+                entry.key().content_hash.is_some()
+                    && entry
+                        .key()
+                        .path()
+                        .is_some_and(|known| paths.iter().any(|path| known.starts_with(path)))
+                    && !entry.key().is_available()
+            })
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+
+    // Drops every source at or under `path` that isn't on disk as recorded anymore, then
+    // reads whatever is there now and wasn't known yet. New files with the same content as
+    // one of the `missing` sources take over its track instead.
+    fn refresh_path(
+        path: &Path,
+        playlist: Option<PlaylistId>,
+        scan_record: &ScanRecord,
+        tx: &Sender<ScannerEvent>,
+        missing: &mut Vec<(TrackSource, TrackId)>,
+        new: &mut Vec<(Track, Option<PlaylistId>)>,
+    ) {
+        let stale: Vec<(TrackSource, TrackId)> = scan_record
+            .iter()
+            .filter(|entry| {
+                entry
+                    .key()
+                    .path()
+                    .is_some_and(|known| known.starts_with(path))
+                    && !is_unchanged(entry.key())
+                    && !missing.iter().any(|(source, _)| source == entry.key())
+            })
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+
+        Self::drop_sources(stale, scan_record, tx);
+
+        let files: Vec<PathBuf> = if path.is_dir() {
+            WalkDir::new(path)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|e| e.file_type().is_file())
+                .map(walkdir::DirEntry::into_path)
+                .collect()
+        } else if path.is_file() {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        };
+
+        let mut split: HashMap<PathBuf, HashSet<PathBuf>> = HashMap::new();
+
+        for file in files {
+            if !(formats::is_playable(&file)
+                || cue::is_cue_sheet(&file)
+                || station::is_station_file(&file))
+            {
+                continue;
+            }
+
+            // Files split up by a CUE sheet are only listed through its entries.
+            if let Some(dir) = file.parent()
+                && split
+                    .entry(dir.to_path_buf())
+                    .or_insert_with(|| split_by_cue(dir))
+                    .contains(&file)
+            {
+                continue;
+            }
+
+            let Ok(sources) = Self::job_sources(&file) else {
+                continue;
+            };
+
+            for (ts, origin) in sources {
+                if scan_record.contains_key(&ts) {
+                    continue;
+                }
+
+                let ts = ts.with_content_hash();
+
+                if let Some(moved) = missing.iter().position(|(old, _)| old.same_content(&ts)) {
+                    let (old, id) = missing.swap_remove(moved);
+                    Self::replace_source(&old, ts, id, scan_record, tx);
+                    continue;
+                }
+
+                if let Ok(track) = Self::read_track(ts.clone(), &origin) {
+                    scan_record.insert(ts, track.id);
+                    new.push((track, playlist));
+                }
+            }
+        }
+    }
+
     fn write_scan_record(&self) {
-        let path = self.app_paths.cache.join("scan_record.bin");
+        Self::save_scan_record(&self.app_paths.cache, &self.scan_record);
+    }
+
+    fn save_scan_record(cache_dir: &Path, scan_record: &ScanRecord) {
+        let tmp_path = cache_dir.join("scan_record.tmp");
+        let final_path = cache_dir.join("scan_record.bin");
 
-        let map: HashMap<CachedTrackSource, [u8; 16]> = self
-            .scan_record
+        let map: CachedScanRecord = scan_record
             .iter()
-            .map(|entry| (entry.key().into(), entry.value().0))
+            .map(|entry| {
+                let cached = CachedScanEntry {
+                    id: entry.value().0,
+                    content_hash: entry.key().content_hash.map(u128::to_le_bytes),
+                };
+
+                (entry.key().into(), cached)
+            })
             .collect();
 
-        let bytes = bitcode::encode(&map);
-        std::fs::write(path, bytes).unwrap();
+        if let Err(e) = write_cache(&tmp_path, &final_path, SCAN_RECORD_VERSION, map) {
+            tracing::error!(error = %e, "Failed to write scan record");
+        }
     }
 
     fn read_scan_record(&self) {
         let path = self.app_paths.cache.join("scan_record.bin");
 
-        let file = std::fs::read(path).ok();
+        let raw = match read_migrated::<LegacyScanRecord>(&path, SCAN_RECORD_VERSION) {
+            Ok(raw) => raw.unwrap_or_default(),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to read scan record");
+                return;
+            }
+        };
+
+        self.scan_record.clear();
+
+        for (k, v) in raw {
+            let source = TrackSource {
+                content_hash: v.content_hash.map(u128::from_le_bytes),
+                ..TrackSource::from(&k)
+            };
+
+            self.scan_record.insert(source, TrackId(v.id));
+        }
+    }
+
+    fn write_loudness_failures(&self) {
+        let tmp_path = self.app_paths.cache.join("loudness_failures.tmp");
+        let final_path = self.app_paths.cache.join("loudness_failures.bin");
 
-        if let Some(bytes) = file {
-            let raw: HashMap<CachedTrackSource, [u8; 16]> =
-                bitcode::decode(&bytes).unwrap_or_default();
+        let failures: CachedLoudnessFailures =
+            self.loudness_failures.iter().map(Into::into).collect();
 
-            let map: HashMap<TrackSource, TrackId> =
-                raw.iter().map(|(k, v)| (k.into(), TrackId(*v))).collect();
+        if let Err(e) = write_cache(&tmp_path, &final_path, LOUDNESS_FAILURES_VERSION, failures) {
+            tracing::error!(error = %e, "Failed to write loudness failures");
+        }
+    }
 
-            self.scan_record.clear();
+    fn read_loudness_failures(&mut self) {
+        let path = self.app_paths.cache.join("loudness_failures.bin");
 
-            for (k, v) in map {
-                self.scan_record.insert(k, v);
+        match read_cache::<CachedLoudnessFailures>(&path, LOUDNESS_FAILURES_VERSION) {
+            Ok(failures) => {
+                self.loudness_failures = failures
+                    .unwrap_or_default()
+                    .iter()
+                    .map(TrackSource::from)
+                    .collect();
             }
+            Err(e) => tracing::error!(error = %e, "Failed to read loudness failures"),
         }
     }
 }
+
+fn is_unchanged(source: &TrackSource) -> bool {
+    source
+        .path()
+        .and_then(|path| TrackSource::generate(path).ok())
+        .is_some_and(|fresh| fresh.size == source.size && fresh.modified == source.modified)
+}
+
+// Every track that still has a source in the scan record.
+fn recorded_tracks(scan_record: &ScanRecord) -> HashSet<TrackId> {
+    scan_record.iter().map(|entry| *entry.value()).collect()
+}
+
+fn split_by_cue(dir: &Path) -> HashSet<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HashSet::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| cue::is_cue_sheet(path))
+        .filter_map(|path| cue::read(&path).ok())
+        .flat_map(|sheet| sheet.files())
+        .collect()
+}
+
+/// Every file under `root` the scanner can read tracks from. `on_found` hears how many
+/// were found so far before each new one.
+fn discover_files(root: &Path, mut on_found: impl FnMut(usize)) -> Vec<PathBuf> {
+    let mut paths = Vec::with_capacity(1024);
+    let mut split = HashSet::new();
+
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+
+        if cue::is_cue_sheet(path) {
+            match cue::read(path) {
+                Ok(sheet) => split.extend(sheet.files()),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping CUE sheet");
+                    continue;
+                }
+            }
+        } else if station::is_station_file(path) {
+            // Most playlists next to music list local files, not streams.
+            if station::read(path).is_err() {
+                continue;
+            }
+        } else if !formats::is_playable(path) {
+            continue;
+        }
+
+        on_found(paths.len());
+        paths.push(path.to_path_buf());
+    }
+
+    // Files split up by a CUE sheet are only listed through its entries.
+    paths.retain(|path| !split.contains(path));
+
+    paths
+}
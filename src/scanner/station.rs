@@ -0,0 +1,110 @@
+use crate::errors::ScannerError;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// One stream listed in an `.m3u` or `.pls` station file.
+#[derive(Debug, Clone)]
+pub struct Station {
+    pub url: String,
+    pub title: Option<String>,
+}
+
+#[must_use]
+pub fn is_station_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("m3u") || ext.eq_ignore_ascii_case("pls"))
+}
+
+/// Reads the streams a station file lists. Local files in an M3U playlist are left out;
+/// only its network streams count.
+#[allow(clippy::missing_errors_doc)]
+pub fn read(path: &Path) -> Result<Vec<Station>, ScannerError> {
+    let bytes = std::fs::read(path)?;
+
+    let text = String::from_utf8(bytes)
+        .unwrap_or_else(|e| e.into_bytes().into_iter().map(char::from).collect());
+    let text = text.trim_start_matches('\u{feff}');
+
+    let is_pls = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pls"));
+
+    let stations = if is_pls {
+        parse_pls(text)
+    } else {
+        parse_m3u(text)
+    };
+
+    if stations.is_empty() {
+        return Err(ScannerError::StationFile("no streams".into()));
+    }
+
+    Ok(stations)
+}
+
+fn parse_m3u(text: &str) -> Vec<Station> {
+    let mut stations = Vec::new();
+    let mut title = None;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // `#EXTINF:-1,Station Name`
+            title = info
+                .split_once(',')
+                .map(|(_, name)| name.trim().to_string())
+                .filter(|name| !name.is_empty());
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else {
+            if is_stream_url(line) {
+                stations.push(Station {
+                    url: line.to_string(),
+                    title: title.take(),
+                });
+            }
+
+            title = None;
+        }
+    }
+
+    stations
+}
+
+// `[playlist]` followed by `File1=`, `Title1=` and so on, numbered per entry.
+fn parse_pls(text: &str) -> Vec<Station> {
+    let mut urls = BTreeMap::new();
+    let mut titles = HashMap::new();
+
+    for line in text.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim().to_string();
+
+        if let Some(number) = key.strip_prefix("file").and_then(|n| n.parse::<u32>().ok()) {
+            urls.insert(number, value);
+        } else if let Some(number) = key
+            .strip_prefix("title")
+            .and_then(|n| n.parse::<u32>().ok())
+        {
+            titles.insert(number, value);
+        }
+    }
+
+    urls.into_iter()
+        .filter(|(_, url)| is_stream_url(url))
+        .map(|(number, url)| Station {
+            url,
+            title: titles.remove(&number).filter(|title| !title.is_empty()),
+        })
+        .collect()
+}
+
+fn is_stream_url(value: &str) -> bool {
+    let lower = value.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
@@ -0,0 +1,107 @@
+use crate::controller::state::PlaylistId;
+use crate::errors::ScannerError;
+use crossbeam_channel::Sender;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// A path is only looked at once it has been quiet for this long, so a file still being
+// copied in or rewritten by a tag editor gets read once, when it's done.
+const SETTLE_AFTER: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    // Something at this path was created, modified or removed.
+    Path(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+/// Watches the roots of folder playlists and collects what changed under them.
+pub struct FolderWatcher {
+    watcher: RecommendedWatcher,
+    roots: HashMap<PathBuf, PlaylistId>,
+
+    pending: HashMap<PathBuf, Instant>,
+    renames: Vec<(PathBuf, PathBuf)>,
+}
+
+impl FolderWatcher {
+    #[allow(clippy::missing_errors_doc)]
+    pub fn new(tx: Sender<notify::Result<Event>>) -> Result<Self, ScannerError> {
+        let watcher = notify::recommended_watcher(move |event| {
+            tx.send(event).ok();
+        })?;
+
+        Ok(FolderWatcher {
+            watcher,
+            roots: HashMap::new(),
+            pending: HashMap::new(),
+            renames: Vec::new(),
+        })
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn watch(&mut self, root: PathBuf, playlist: PlaylistId) -> Result<(), ScannerError> {
+        if !self.roots.contains_key(&root) {
+            self.watcher.watch(&root, RecursiveMode::Recursive)?;
+        }
+
+        self.roots.insert(root, playlist);
+
+        Ok(())
+    }
+
+    /// The folder playlist whose root holds `path`, the innermost one if roots are nested.
+    #[must_use]
+    pub fn playlist_for(&self, path: &Path) -> Option<PlaylistId> {
+        self.roots
+            .iter()
+            .filter(|(root, _)| path.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+            .map(|(_, playlist)| *playlist)
+    }
+
+    pub fn handle(&mut self, event: Event) {
+        let now = Instant::now();
+
+        match event.kind {
+            EventKind::Access(_) => {}
+            // The separate `From` and `To` events for the same move came first, and would
+            // otherwise read it as a removal and an unrelated new file.
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = event.paths.as_slice() {
+                    self.pending.remove(from);
+                    self.pending.remove(to);
+                    self.renames.push((from.clone(), to.clone()));
+                }
+            }
+            _ => {
+                for path in event.paths {
+                    self.pending.insert(path, now);
+                }
+            }
+        }
+    }
+
+    /// Takes the renames seen so far and the paths that have stopped changing.
+    pub fn settled(&mut self) -> Vec<Change> {
+        let mut changes: Vec<Change> = self
+            .renames
+            .drain(..)
+            .map(|(from, to)| Change::Renamed { from, to })
+            .collect();
+
+        self.pending.retain(|path, changed| {
+            if changed.elapsed() < SETTLE_AFTER {
+                return true;
+            }
+
+            changes.push(Change::Path(path.clone()));
+            false
+        });
+
+        changes
+    }
+}
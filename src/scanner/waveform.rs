@@ -0,0 +1,63 @@
+use crate::audio::formats;
+use crate::audio::span::Span;
+use crate::controller::state::{TrackSource, Waveform};
+use crate::errors::ScannerError;
+use rodio::Source;
+use std::fs::File;
+
+const POINTS: usize = 1000;
+
+#[allow(clippy::missing_errors_doc)]
+pub fn summarize(source: &TrackSource) -> Result<Waveform, ScannerError> {
+    let path = source.path().ok_or(ScannerError::StreamSource)?;
+    let decoder = formats::decoder_builder::<File>(path)?
+        .with_seekable(true)
+        .build()?;
+    let decoder = Span::new(decoder, source.range)?;
+
+    let channels = usize::from(decoder.channels().get());
+    let rate = f64::from(decoder.sample_rate().get());
+
+    // Without a known duration, collect 10ms blocks and merge them down afterwards.
+    let block_frames = match decoder.total_duration() {
+        Some(duration) => (duration.as_secs_f64() * rate) as usize / POINTS,
+        None => (rate / 100.0) as usize,
+    }
+    .max(1);
+    let block_len = block_frames * channels;
+
+    let mut blocks: Vec<(f32, f32)> = Vec::with_capacity(POINTS + 1);
+    let mut current = (0f32, 0f32);
+
+    for (i, sample) in decoder.enumerate() {
+        current = (current.0.min(sample), current.1.max(sample));
+
+        if (i + 1) % block_len == 0 {
+            blocks.push(current);
+            current = (0.0, 0.0);
+        }
+    }
+
+    if current != (0.0, 0.0) {
+        blocks.push(current);
+    }
+
+    if blocks.len() <= POINTS {
+        return Ok(Waveform { peaks: blocks });
+    }
+
+    let peaks = (0..POINTS)
+        .map(|point| {
+            let start = point * blocks.len() / POINTS;
+            let end = ((point + 1) * blocks.len() / POINTS).max(start + 1);
+
+            blocks[start..end]
+                .iter()
+                .fold((0f32, 0f32), |(min, max), (lo, hi)| {
+                    (min.min(*lo), max.max(*hi))
+                })
+        })
+        .collect();
+
+    Ok(Waveform { peaks })
+}
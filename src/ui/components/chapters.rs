@@ -0,0 +1,105 @@
+use crate::controller::Controller;
+use crate::ui::theme::Theme;
+use gpui::prelude::FluentBuilder;
+use gpui::{
+    App, AppContext, Context, Entity, FontWeight, InteractiveElement, IntoElement, ParentElement,
+    Render, StatefulInteractiveElement, Styled, Window, div,
+};
+use std::time::Duration;
+
+/// Chapters of the current track; clicking one seeks to it.
+pub struct ChapterList;
+
+impl ChapterList {
+    pub fn new(cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| {
+            let state = cx.global::<Controller>().state.clone();
+            cx.observe(&state, |_, _, cx| cx.notify()).detach();
+
+            Self
+        })
+    }
+}
+
+impl Render for ChapterList {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = *cx.global::<Theme>();
+        let state = cx.global::<Controller>().state.read(cx);
+
+        let Some(track) = state
+            .playback
+            .current
+            .and_then(|id| state.library.tracks.get(&id))
+            .filter(|track| !track.chapters.is_empty())
+        else {
+            return div()
+                .id("chapters")
+                .size_full()
+                .flex()
+                .items_center()
+                .justify_center()
+                .text_sm()
+                .text_color(theme.queue_item_artist)
+                .child("No chapters");
+        };
+
+        let current = track.chapter_at(state.playback.position);
+
+        div()
+            .id("chapters")
+            .size_full()
+            .py_2()
+            .flex()
+            .flex_col()
+            .overflow_y_scroll()
+            .children(track.chapters.iter().enumerate().map(|(i, chapter)| {
+                let start = chapter.start;
+                let is_current = current == Some(i);
+
+                div()
+                    .id(("chapter", i))
+                    .w_full()
+                    .flex()
+                    .items_center()
+                    .gap_x_4()
+                    .px_3()
+                    .py_2()
+                    .mb_1()
+                    .rounded_lg()
+                    .cursor_pointer()
+                    .hover(|this| this.bg(theme.queue_item_bg_hover))
+                    .when(is_current, |this| this.bg(theme.queue_item_bg_current))
+                    .on_click(move |_, _, cx| cx.global::<Controller>().seek(start))
+                    .child(
+                        div()
+                            .flex_1()
+                            .truncate()
+                            .text_color(if is_current {
+                                theme.queue_item_title_current
+                            } else {
+                                theme.queue_item_title
+                            })
+                            .when(is_current, |this| this.font_weight(FontWeight::MEDIUM))
+                            .child(chapter.title.clone()),
+                    )
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .text_sm()
+                            .font_family("JetBrains Mono")
+                            .text_color(theme.queue_item_artist)
+                            .child(format_start(start)),
+                    )
+            }))
+    }
+}
+
+fn format_start(start: Duration) -> String {
+    let secs = start.as_secs();
+
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -1,13 +1,21 @@
-use crate::controller::Controller;
+use crate::controller::state::{FadeCurve, ReplayGainMode, ResumeOnLaunch, SleepTimer};
+use crate::controller::{Controller, next_bookmark};
+use crate::ui::helpers::{duration_to_slider, local_time_of_day};
 use crate::ui::theme::Theme;
+use std::time::Duration;
 
 use super::slider::{Slider, SliderState};
 use crate::ui::components::icons::{Icon, Icons};
+use gpui::prelude::FluentBuilder;
 use gpui::{
     Context, Entity, InteractiveElement, IntoElement, ParentElement, Render,
     StatefulInteractiveElement, Styled, Window, div,
 };
 
+const CROSSFADE_STEPS: [u64; 6] = [0, 2, 4, 6, 8, 12];
+const SPEED_STEPS: [f32; 6] = [0.5, 0.75, 1.0, 1.25, 1.5, 2.0];
+const RESUME_THRESHOLD_STEPS: [u64; 5] = [0, 10, 20, 30, 60];
+
 #[derive(Clone)]
 pub struct ControlBar {
     pub playback_slider_state: Entity<SliderState>,
@@ -46,6 +54,65 @@ impl Render for ControlBar {
             0
         };
 
+        let track_duration = current.map(|track| track.duration).unwrap_or_default();
+
+        let loop_range =
+            state
+                .playback
+                .loop_start
+                .zip(state.playback.loop_end)
+                .map(|(start, end)| {
+                    (
+                        duration_to_slider(start, track_duration),
+                        duration_to_slider(end, track_duration),
+                    )
+                });
+
+        let bookmarks = current
+            .and_then(|track| state.bookmarks.tracks.get(&track.id))
+            .cloned()
+            .unwrap_or_default();
+
+        let markers = bookmarks
+            .iter()
+            .map(|bookmark| bookmark.position)
+            .chain(state.playback.loop_start.filter(|_| loop_range.is_none()))
+            .map(|position| duration_to_slider(position, track_duration))
+            .collect();
+
+        let chapter_ticks = current
+            .map(|track| {
+                track
+                    .chapters
+                    .iter()
+                    .filter(|chapter| !chapter.start.is_zero())
+                    .map(|chapter| duration_to_slider(chapter.start, track_duration))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let crossfade = state.playback.crossfade;
+        let resume_threshold = state.playback.resume_threshold;
+        let alarm_label = state.alarm.alarm.map(|alarm| {
+            let (hour, minute) = local_time_of_day(alarm.at);
+
+            match state.library.playlists.get(&alarm.playlist) {
+                Some(playlist) => format!("Alarm {hour:02}:{minute:02} · {}", playlist.name),
+                None => format!("Alarm {hour:02}:{minute:02}"),
+            }
+        });
+        let sleep_label = match state.playback.sleep_timer {
+            None => "Sleep off".to_string(),
+            Some(SleepTimer::After { .. }) => {
+                let remaining = controller.sleep_remaining(cx).unwrap_or_default();
+                format!("Sleep in {}m", remaining.as_secs().div_ceil(60))
+            }
+            Some(SleepTimer::EndOfTrack) => "Sleep after track".to_string(),
+            Some(SleepTimer::EndOfQueue) => "Sleep after queue".to_string(),
+        };
+        let icons_bg_hover = theme.player_icons_bg_hover;
+        let icons_text_hover = theme.player_icons_text_hover;
+
         div()
             .w_full()
             .h_auto()
@@ -66,7 +133,13 @@ impl Render for ControlBar {
                     .child(
                         Slider::new(&self.playback_slider_state.clone(), "playback_slider", 6.0)
                             .text_color(theme.playback_slider_fill)
-                            .bg(theme.playback_slider_track),
+                            .bg(theme.playback_slider_track)
+                            .range(loop_range)
+                            .markers(markers)
+                            .marker_color(theme.playback_slider_loop)
+                            .ticks(chapter_ticks)
+                            .tick_color(theme.playback_slider_chapter)
+                            .waveform(state.playback.waveform.clone()),
                     )
                     .child(
                         div()
@@ -148,8 +221,459 @@ impl Render for ControlBar {
                                             .text_color(theme.volume_slider_fill),
                                         ),
                                     ),
+                            )
+                            .child(
+                                div()
+                                    .w_auto()
+                                    .h_auto()
+                                    .flex()
+                                    .flex_shrink_0()
+                                    .items_center()
+                                    .justify_end()
+                                    .gap_x_2()
+                                    .pt_2()
+                                    .font_family("JetBrains Mono")
+                                    .text_sm()
+                                    .text_color(theme.playback_position_text)
+                                    .child(
+                                        div()
+                                            .id("ab_loop")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| controller.cycle_ab_loop(cx)
+                                            })
+                                            .child(
+                                                match (
+                                                    state.playback.loop_start,
+                                                    state.playback.loop_end,
+                                                ) {
+                                                    (None, _) => "Loop A".to_string(),
+                                                    (Some(start), None) => {
+                                                        format!("Loop {} - B", format_time(start))
+                                                    }
+                                                    (Some(start), Some(end)) => format!(
+                                                        "Loop {} - {}",
+                                                        format_time(start),
+                                                        format_time(end)
+                                                    ),
+                                                },
+                                            ),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("add_bookmark")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                let count = bookmarks.len();
+                                                move |_, _, cx| {
+                                                    controller.add_bookmark(
+                                                        format!("Bookmark {}", count + 1),
+                                                        cx,
+                                                    );
+                                                }
+                                            })
+                                            .child("Bookmark"),
+                                    )
+                                    .when_some(
+                                        next_bookmark(&bookmarks, state.playback.position),
+                                        |this, bookmark| {
+                                            this.child(
+                                                div()
+                                                    .id("next_bookmark")
+                                                    .px_2()
+                                                    .rounded_md()
+                                                    .cursor_pointer()
+                                                    .hover(|this| {
+                                                        this.bg(icons_bg_hover)
+                                                            .text_color(icons_text_hover)
+                                                    })
+                                                    .on_click({
+                                                        let controller = controller.clone();
+                                                        move |_, _, cx| {
+                                                            controller.jump_to_next_bookmark(cx);
+                                                        }
+                                                    })
+                                                    .child(format!("Jump to {}", bookmark.name)),
+                                            )
+                                        },
+                                    )
+                                    .child(
+                                        div()
+                                            .id("crossfade")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let playback =
+                                                        controller.state.read(cx).playback.clone();
+
+                                                    controller.set_crossfade(
+                                                        next_crossfade_step(playback.crossfade),
+                                                        playback.crossfade_curve,
+                                                        cx,
+                                                    );
+                                                }
+                                            })
+                                            .child(if crossfade.is_zero() {
+                                                "Crossfade off".to_string()
+                                            } else {
+                                                format!("Crossfade {}s", crossfade.as_secs())
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("crossfade_curve")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let playback =
+                                                        controller.state.read(cx).playback.clone();
+
+                                                    let curve = match playback.crossfade_curve {
+                                                        FadeCurve::Linear => FadeCurve::EqualPower,
+                                                        FadeCurve::EqualPower => FadeCurve::Linear,
+                                                    };
+
+                                                    controller.set_crossfade(
+                                                        playback.crossfade,
+                                                        curve,
+                                                        cx,
+                                                    );
+                                                }
+                                            })
+                                            .child(match state.playback.crossfade_curve {
+                                                FadeCurve::Linear => "Linear",
+                                                FadeCurve::EqualPower => "Equal power",
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("replay_gain_mode")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let mode = match controller
+                                                        .state
+                                                        .read(cx)
+                                                        .playback
+                                                        .replay_gain_mode
+                                                    {
+                                                        ReplayGainMode::Track => {
+                                                            ReplayGainMode::Album
+                                                        }
+                                                        ReplayGainMode::Album => {
+                                                            ReplayGainMode::Off
+                                                        }
+                                                        ReplayGainMode::Off => {
+                                                            ReplayGainMode::Track
+                                                        }
+                                                    };
+
+                                                    controller.set_replay_gain_mode(mode, cx);
+                                                }
+                                            })
+                                            .child(match state.playback.replay_gain_mode {
+                                                ReplayGainMode::Track => "ReplayGain track",
+                                                ReplayGainMode::Album => "ReplayGain album",
+                                                ReplayGainMode::Off => "ReplayGain off",
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("resume_on_launch")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let mode = match controller
+                                                        .state
+                                                        .read(cx)
+                                                        .playback
+                                                        .resume_on_launch
+                                                    {
+                                                        ResumeOnLaunch::Paused => {
+                                                            ResumeOnLaunch::Play
+                                                        }
+                                                        ResumeOnLaunch::Play => {
+                                                            ResumeOnLaunch::Fresh
+                                                        }
+                                                        ResumeOnLaunch::Fresh => {
+                                                            ResumeOnLaunch::Paused
+                                                        }
+                                                    };
+
+                                                    controller.set_resume_on_launch(mode, cx);
+                                                }
+                                            })
+                                            .child(match state.playback.resume_on_launch {
+                                                ResumeOnLaunch::Paused => "Resume paused",
+                                                ResumeOnLaunch::Play => "Resume playing",
+                                                ResumeOnLaunch::Fresh => "Start fresh",
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("resume_threshold")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let threshold = controller
+                                                        .state
+                                                        .read(cx)
+                                                        .playback
+                                                        .resume_threshold;
+
+                                                    controller.set_resume_threshold(
+                                                        next_resume_threshold_step(threshold),
+                                                        cx,
+                                                    );
+                                                }
+                                            })
+                                            .child(if resume_threshold.is_zero() {
+                                                "Track resume off".to_string()
+                                            } else {
+                                                format!(
+                                                    "Resume tracks over {}m",
+                                                    resume_threshold.as_secs() / 60
+                                                )
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("skip_chapters")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let skip = controller
+                                                        .state
+                                                        .read(cx)
+                                                        .playback
+                                                        .skip_chapters;
+
+                                                    controller.set_skip_chapters(!skip, cx);
+                                                }
+                                            })
+                                            .child(if state.playback.skip_chapters {
+                                                "Skip chapters"
+                                            } else {
+                                                "Skip tracks"
+                                            }),
+                                    )
+                                    .when_some(alarm_label, |this, label| {
+                                        this.child(
+                                            div()
+                                                .id("alarm")
+                                                .px_2()
+                                                .max_w_48()
+                                                .truncate()
+                                                .rounded_md()
+                                                .cursor_pointer()
+                                                .hover(|this| {
+                                                    this.bg(icons_bg_hover)
+                                                        .text_color(icons_text_hover)
+                                                })
+                                                .on_click({
+                                                    let controller = controller.clone();
+                                                    move |_, _, cx| controller.set_alarm(None, cx)
+                                                })
+                                                .child(label),
+                                        )
+                                    })
+                                    .child(
+                                        div()
+                                            .id("sleep_timer")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    controller.cycle_sleep_timer(cx);
+                                                }
+                                            })
+                                            .child(sleep_label),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("speed")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let playback =
+                                                        controller.state.read(cx).playback.clone();
+
+                                                    controller.set_speed(
+                                                        next_speed_step(playback.speed),
+                                                        playback.preserve_pitch,
+                                                        cx,
+                                                    );
+                                                }
+                                            })
+                                            .child(format!("Speed {}x", state.playback.speed)),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("preserve_pitch")
+                                            .px_2()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let playback =
+                                                        controller.state.read(cx).playback.clone();
+
+                                                    controller.set_speed(
+                                                        playback.speed,
+                                                        !playback.preserve_pitch,
+                                                        cx,
+                                                    );
+                                                }
+                                            })
+                                            .child(if state.playback.preserve_pitch {
+                                                "Keep pitch"
+                                            } else {
+                                                "Shift pitch"
+                                            }),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("output_device")
+                                            .px_2()
+                                            .max_w_48()
+                                            .truncate()
+                                            .rounded_md()
+                                            .cursor_pointer()
+                                            .hover(|this| {
+                                                this.bg(icons_bg_hover).text_color(icons_text_hover)
+                                            })
+                                            .on_click({
+                                                let controller = controller.clone();
+                                                move |_, _, cx| {
+                                                    let playback =
+                                                        controller.state.read(cx).playback.clone();
+
+                                                    controller.set_output_device(
+                                                        next_output_device(
+                                                            &playback.output_devices,
+                                                            playback.output_device.as_ref(),
+                                                        ),
+                                                        cx,
+                                                    );
+                                                }
+                                            })
+                                            .child(match &state.playback.active_output {
+                                                Some(name) => format!("Output: {name}"),
+                                                None => "Output: none".to_string(),
+                                            }),
+                                    ),
                             ),
                     ),
             )
     }
 }
+
+fn format_time(position: Duration) -> String {
+    format!(
+        "{:02}:{:02}",
+        position.as_secs() / 60,
+        position.as_secs() % 60
+    )
+}
+
+fn next_crossfade_step(current: Duration) -> Duration {
+    let secs = current.as_secs();
+
+    let next = CROSSFADE_STEPS
+        .iter()
+        .copied()
+        .find(|&step| step > secs)
+        .unwrap_or(0);
+
+    Duration::from_secs(next)
+}
+
+fn next_resume_threshold_step(current: Duration) -> Duration {
+    let mins = current.as_secs() / 60;
+
+    let next = RESUME_THRESHOLD_STEPS
+        .iter()
+        .copied()
+        .find(|&step| step > mins)
+        .unwrap_or(0);
+
+    Duration::from_secs(next * 60)
+}
+
+fn next_speed_step(current: f32) -> f32 {
+    SPEED_STEPS
+        .iter()
+        .copied()
+        .find(|&step| step > current + f32::EPSILON)
+        .unwrap_or(SPEED_STEPS[0])
+}
+
+fn next_output_device(devices: &[String], current: Option<&String>) -> Option<String> {
+    match current.and_then(|name| devices.iter().position(|d| d == name)) {
+        None => devices.first().cloned(),
+        Some(i) => devices.get(i + 1).cloned(),
+    }
+}
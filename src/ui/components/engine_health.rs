@@ -0,0 +1,76 @@
+use crate::controller::state::{Engine, EngineHealth};
+use crate::ui::theme::Theme;
+use gpui::{
+    App, AppContext, Context, Entity, Global, IntoElement, ParentElement, Render, Styled, Window,
+    div,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct EngineHealthInner {
+    pub engines: HashMap<Engine, EngineHealth>,
+}
+
+pub struct EngineHealthState(pub Entity<EngineHealthInner>);
+
+impl Global for EngineHealthState {}
+
+/// Titlebar badge listing engines that aren't running normally; empty while all is well.
+pub struct EngineHealthIndicator;
+
+impl EngineHealthIndicator {
+    pub fn new(cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| {
+            let state = cx.global::<EngineHealthState>().0.clone();
+            cx.observe(&state, |_, _, cx| cx.notify()).detach();
+
+            Self
+        })
+    }
+}
+
+impl Render for EngineHealthIndicator {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = *cx.global::<Theme>();
+        let state = cx.global::<EngineHealthState>().0.read(cx);
+
+        let mut degraded: Vec<(Engine, String, bool)> = state
+            .engines
+            .iter()
+            .filter_map(|(engine, health)| match health {
+                EngineHealth::Running | EngineHealth::Stopped => None,
+                EngineHealth::Restarting { attempt, .. } => Some((
+                    *engine,
+                    format!("{} restarting ({attempt})", engine.name()),
+                    false,
+                )),
+                EngineHealth::Failed(_) => Some((*engine, format!("{} down", engine.name()), true)),
+            })
+            .collect();
+        degraded.sort_by_key(|(engine, ..)| engine.name());
+
+        div()
+            .flex()
+            .items_center()
+            .gap_x_2()
+            .flex_shrink_0()
+            .text_xs()
+            .children(degraded.into_iter().map(|(_, label, failed)| {
+                let color = if failed {
+                    theme.toast_error_accent
+                } else {
+                    theme.titlebar_engine_restarting
+                };
+
+                div()
+                    .px_2()
+                    .py_0p5()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(color)
+                    .text_color(color)
+                    .whitespace_nowrap()
+                    .child(label)
+            }))
+    }
+}
@@ -0,0 +1,297 @@
+use crate::controller::Controller;
+use crate::controller::state::EqBand;
+use crate::ui::components::slider::{Slider, SliderEvent, SliderState};
+use crate::ui::theme::Theme;
+use gpui::prelude::FluentBuilder;
+use gpui::{
+    App, AppContext, Context, Entity, FontWeight, InteractiveElement, IntoElement, ParentElement,
+    Render, StatefulInteractiveElement, Styled, Window, div, px,
+};
+
+const CUSTOM_PRESET: &str = "Custom";
+
+pub struct EqualizerPanel {
+    sliders: Vec<Entity<SliderState>>,
+}
+
+impl EqualizerPanel {
+    pub fn new(cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| {
+            let sliders = (0..EqBand::FREQUENCIES.len())
+                .map(|index| {
+                    let slider = cx.new(|_| {
+                        SliderState::new()
+                            .min(-12.0)
+                            .max(12.0)
+                            .default_value(0.0)
+                            .step(0.5)
+                    });
+
+                    cx.subscribe(&slider, move |_, _, event: &SliderEvent, cx| match event {
+                        SliderEvent::Change(value) => {
+                            let controller = cx.global::<Controller>().clone();
+
+                            controller.set_eq_band_gain(index, *value, cx);
+                            cx.notify();
+                        }
+                    })
+                    .detach();
+
+                    slider
+                })
+                .collect();
+
+            Self { sliders }
+        })
+    }
+
+    pub fn sync(&self, cx: &mut App) {
+        sync_sliders(&self.sliders, cx);
+    }
+}
+
+fn sync_sliders(sliders: &[Entity<SliderState>], cx: &mut App) {
+    let bands = cx
+        .global::<Controller>()
+        .state
+        .read(cx)
+        .equalizer
+        .bands
+        .clone();
+
+    for (slider, band) in sliders.iter().zip(bands) {
+        slider.update(cx, |this, cx| this.set_value(band.gain, cx));
+    }
+}
+
+fn next_preset(presets: &[String], current: Option<&String>) -> Option<String> {
+    match current.and_then(|name| presets.iter().position(|p| p == name)) {
+        None => presets.first().cloned(),
+        Some(i) => presets.get(i + 1).cloned(),
+    }
+}
+
+fn format_frequency(frequency: f32) -> String {
+    if frequency >= 1000.0 {
+        format!("{}k", frequency / 1000.0)
+    } else {
+        format!("{frequency}")
+    }
+}
+
+impl Render for EqualizerPanel {
+    #[allow(clippy::too_many_lines)]
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = *cx.global::<Theme>();
+        let controller = cx.global::<Controller>().clone();
+        let state = controller.state.read(cx);
+        let equalizer = state.equalizer.clone();
+
+        let current = state
+            .playback
+            .current
+            .and_then(|id| state.library.tracks.get(&id))
+            .cloned();
+
+        let preset_names: Vec<String> = equalizer.presets.iter().map(|p| p.name.clone()).collect();
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_y_4()
+            .py_4()
+            .text_sm()
+            .text_color(theme.player_panel_tab_text)
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .font_weight(FontWeight(500.0))
+                            .text_color(theme.player_panel_tab_text_active)
+                            .child("Equalizer"),
+                    )
+                    .child(
+                        div()
+                            .id("eq_enabled")
+                            .px_2()
+                            .rounded_md()
+                            .cursor_pointer()
+                            .when(equalizer.enabled, |this| {
+                                this.bg(theme.player_icons_bg_active)
+                                    .text_color(theme.player_icons_text_active)
+                            })
+                            .hover(|this| {
+                                this.bg(theme.player_icons_bg_hover)
+                                    .text_color(theme.player_icons_text_hover)
+                            })
+                            .on_click({
+                                let controller = controller.clone();
+                                move |_, _, cx| {
+                                    let enabled = controller.state.read(cx).equalizer.enabled;
+                                    controller.set_eq_enabled(!enabled, cx);
+                                }
+                            })
+                            .child(if equalizer.enabled { "On" } else { "Off" }),
+                    ),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_wrap()
+                    .gap_2()
+                    .children(equalizer.presets.iter().map(|preset| {
+                        let name = preset.name.clone();
+                        let active = equalizer.preset.as_ref() == Some(&preset.name);
+                        let sliders = self.sliders.clone();
+                        let controller = controller.clone();
+
+                        div()
+                            .id(format!("eq_preset_{name}"))
+                            .px_2()
+                            .rounded_md()
+                            .cursor_pointer()
+                            .when(active, |this| {
+                                this.bg(theme.player_icons_bg_active)
+                                    .text_color(theme.player_icons_text_active)
+                            })
+                            .hover(|this| {
+                                this.bg(theme.player_icons_bg_hover)
+                                    .text_color(theme.player_icons_text_hover)
+                            })
+                            .on_click({
+                                let name = name.clone();
+                                move |_, _, cx| {
+                                    controller.load_eq_preset(&name, cx);
+                                    sync_sliders(&sliders, cx);
+                                }
+                            })
+                            .child(name)
+                    }))
+                    .child(
+                        div()
+                            .id("eq_save_preset")
+                            .px_2()
+                            .rounded_md()
+                            .cursor_pointer()
+                            .hover(|this| {
+                                this.bg(theme.player_icons_bg_hover)
+                                    .text_color(theme.player_icons_text_hover)
+                            })
+                            .on_click({
+                                let controller = controller.clone();
+                                move |_, _, cx| controller.save_eq_preset(CUSTOM_PRESET, cx)
+                            })
+                            .child(format!("Save as {CUSTOM_PRESET}")),
+                    ),
+            )
+            .child(
+                div().w_full().flex().flex_col().gap_y_1().children(
+                    self.sliders
+                        .iter()
+                        .zip(equalizer.bands.iter())
+                        .enumerate()
+                        .map(|(i, (slider, band))| {
+                            div()
+                                .w_full()
+                                .flex()
+                                .items_center()
+                                .gap_x_3()
+                                .font_family("JetBrains Mono")
+                                .child(
+                                    div()
+                                        .w(px(40.0))
+                                        .flex_shrink_0()
+                                        .child(format_frequency(band.frequency)),
+                                )
+                                .child(
+                                    div().flex_1().child(
+                                        Slider::new(slider, format!("eq_band_{i}"), 4.0)
+                                            .bg(theme.volume_slider_track)
+                                            .text_color(theme.volume_slider_fill),
+                                    ),
+                                )
+                                .child(
+                                    div()
+                                        .w(px(64.0))
+                                        .flex_shrink_0()
+                                        .text_color(theme.playback_position_text)
+                                        .child(format!("{:+.1} dB", band.gain)),
+                                )
+                        }),
+                ),
+            )
+            .when_some(current, |this, track| {
+                let track_preset = equalizer.track_presets.get(&track.id).cloned();
+                let album_preset = equalizer.album_presets.get(&track.album).cloned();
+
+                this.child(
+                    div()
+                        .w_full()
+                        .flex()
+                        .flex_col()
+                        .gap_y_1()
+                        .child(
+                            div()
+                                .id("eq_track_preset")
+                                .px_2()
+                                .rounded_md()
+                                .cursor_pointer()
+                                .hover(|this| {
+                                    this.bg(theme.player_icons_bg_hover)
+                                        .text_color(theme.player_icons_text_hover)
+                                })
+                                .on_click({
+                                    let controller = controller.clone();
+                                    let preset_names = preset_names.clone();
+                                    let track_preset = track_preset.clone();
+                                    move |_, _, cx| {
+                                        controller.set_track_eq_preset(
+                                            track.id,
+                                            next_preset(&preset_names, track_preset.as_ref()),
+                                            cx,
+                                        );
+                                    }
+                                })
+                                .child(format!(
+                                    "This track: {}",
+                                    track_preset.as_deref().unwrap_or("Default")
+                                )),
+                        )
+                        .child(
+                            div()
+                                .id("eq_album_preset")
+                                .px_2()
+                                .rounded_md()
+                                .cursor_pointer()
+                                .hover(|this| {
+                                    this.bg(theme.player_icons_bg_hover)
+                                        .text_color(theme.player_icons_text_hover)
+                                })
+                                .on_click({
+                                    let controller = controller.clone();
+                                    let album = track.album.clone();
+                                    let album_preset = album_preset.clone();
+                                    move |_, _, cx| {
+                                        controller.set_album_eq_preset(
+                                            album.clone(),
+                                            next_preset(&preset_names, album_preset.as_ref()),
+                                            cx,
+                                        );
+                                    }
+                                })
+                                .child(format!(
+                                    "This album: {}",
+                                    album_preset.as_deref().unwrap_or("Default")
+                                )),
+                        ),
+                )
+            })
+    }
+}
@@ -84,7 +84,8 @@ impl Render for LyricLineView {
         let state = cx.global::<Controller>().state.read(cx);
         let theme = *cx.global::<Theme>();
 
-        let playback = state.playback.position + Duration::from_millis(80);
+        let playback =
+            state.playback.position + Duration::from_millis(80).mul_f32(state.playback.speed);
 
         let lyrics = cx.global::<LyricsState>().0.read(cx).lyrics.clone();
 
@@ -1,6 +1,9 @@
 pub mod bounds_observer;
+pub mod chapters;
 pub mod controlbar;
 mod element_ext;
+pub mod engine_health;
+pub mod equalizer;
 pub mod icons;
 pub mod image_cache;
 pub mod lyrics;
@@ -11,6 +14,7 @@ pub mod slider;
 pub mod titlebar;
 pub mod toasts;
 pub mod virtual_list;
+pub mod visualizer;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Page {
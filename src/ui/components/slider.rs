@@ -1,10 +1,16 @@
+use crate::controller::state::Waveform;
 use crate::ui::components::element_ext::ElementExt;
+use gpui::prelude::FluentBuilder;
 use gpui::{
-    App, AppContext, Bounds, Context, DragMoveEvent, Entity, EntityId, EventEmitter,
+    App, AppContext, Bounds, Context, DragMoveEvent, Entity, EntityId, EventEmitter, Hsla,
     InteractiveElement, IntoElement, MouseButton, MouseDownEvent, ParentElement as _, Pixels,
     Point, Refineable, Render, RenderOnce, SharedString, StatefulInteractiveElement,
-    StyleRefinement, Styled, Window, div, px, relative, transparent_black, white,
+    StyleRefinement, Styled, Window, black, div, px, relative, transparent_black, white,
 };
+use std::sync::Arc;
+
+const WAVEFORM_COLUMNS: usize = 180;
+const WAVEFORM_HEIGHT: f32 = 32.0;
 
 pub enum SliderEvent {
     Change(f32),
@@ -127,6 +133,12 @@ pub struct Slider {
     style: StyleRefinement,
     id: SharedString,
     height: Pixels,
+    range: Option<(f32, f32)>,
+    markers: Vec<f32>,
+    marker_color: Option<Hsla>,
+    ticks: Vec<f32>,
+    tick_color: Option<Hsla>,
+    waveform: Option<Arc<Waveform>>,
 }
 
 impl Slider {
@@ -136,8 +148,51 @@ impl Slider {
             style: StyleRefinement::default(),
             id: id.into(),
             height: px(height),
+            range: None,
+            markers: Vec::new(),
+            marker_color: None,
+            ticks: Vec::new(),
+            tick_color: None,
+            waveform: None,
         }
     }
+
+    #[must_use]
+    pub fn range(mut self, range: Option<(f32, f32)>) -> Self {
+        self.range = range;
+        self
+    }
+
+    #[must_use]
+    pub fn markers(mut self, markers: Vec<f32>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    #[must_use]
+    pub fn waveform(mut self, waveform: Option<Arc<Waveform>>) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    #[must_use]
+    pub fn marker_color(mut self, color: impl Into<Hsla>) -> Self {
+        self.marker_color = Some(color.into());
+        self
+    }
+
+    /// Thin dividers drawn inside the bar, for section boundaries such as chapters.
+    #[must_use]
+    pub fn ticks(mut self, ticks: Vec<f32>) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    #[must_use]
+    pub fn tick_color(mut self, color: impl Into<Hsla>) -> Self {
+        self.tick_color = Some(color.into());
+        self
+    }
 }
 
 impl Styled for Slider {
@@ -148,7 +203,27 @@ impl Styled for Slider {
 
 impl RenderOnce for Slider {
     fn render(self, window: &mut Window, cx: &mut App) -> impl IntoElement {
-        let percentage = self.state.read(cx).percentage;
+        let state = self.state.read(cx);
+        let percentage = state.percentage;
+
+        let range = self.range.map(|(start, end)| {
+            (
+                state.value_to_percentage(start).clamp(0.0, 1.0),
+                state.value_to_percentage(end).clamp(0.0, 1.0),
+            )
+        });
+
+        let markers: Vec<f32> = self
+            .markers
+            .iter()
+            .map(|marker| state.value_to_percentage(*marker).clamp(0.0, 1.0))
+            .collect();
+
+        let ticks: Vec<f32> = self
+            .ticks
+            .iter()
+            .map(|tick| state.value_to_percentage(*tick).clamp(0.0, 1.0))
+            .collect();
 
         let bar_color = self
             .style
@@ -164,9 +239,20 @@ impl RenderOnce for Slider {
             .and_then(|t| t.color.map(|this| this.to_hsla()))
             .unwrap_or_else(white);
 
+        let marker_color = self.marker_color.unwrap_or_else(|| fill_color.opacity(0.4));
+        let tick_color = self.tick_color.unwrap_or_else(|| black().opacity(0.6));
+
+        let columns = self.waveform.as_deref().map(waveform_columns);
+        let height = if columns.is_some() {
+            px(WAVEFORM_HEIGHT)
+        } else {
+            self.height
+        };
+        let hit_height = height.max(px(24.));
+
         let mut root = div()
             .id(("slider", self.state.entity_id()))
-            .h(px(24.))
+            .h(hit_height)
             .w_full()
             .flex()
             .items_center();
@@ -181,7 +267,7 @@ impl RenderOnce for Slider {
                 .relative()
                 .cursor_pointer()
                 .w_full()
-                .h(px(24.))
+                .h(hit_height)
                 .flex()
                 .items_center()
                 .on_mouse_down(
@@ -213,18 +299,67 @@ impl RenderOnce for Slider {
                         .id("inner_visual_bar")
                         .relative()
                         .w_full()
-                        .h(self.height)
-                        .bg(bar_color)
-                        .rounded_full()
-                        .child(
+                        .h(height)
+                        .when_some(columns, |this, columns| {
+                            let count = columns.len() as f32;
+
+                            this.flex().items_center().gap_x(px(1.)).children(
+                                columns.into_iter().enumerate().map(|(i, amplitude)| {
+                                    let played = (i as f32 + 0.5) / count <= percentage;
+
+                                    div()
+                                        .flex_1()
+                                        .h(relative(amplitude.max(0.04)))
+                                        .rounded_full()
+                                        .bg(if played {
+                                            fill_color
+                                        } else {
+                                            fill_color.opacity(0.25)
+                                        })
+                                }),
+                            )
+                        })
+                        .when(self.waveform.is_none(), |this| {
+                            this.bg(bar_color).rounded_full().child(
+                                div()
+                                    .absolute()
+                                    .left(px(0.))
+                                    .right(relative(1.0 - percentage))
+                                    .h_full()
+                                    .bg(fill_color)
+                                    .rounded_full(),
+                            )
+                        })
+                        .when_some(range, |this, (start, end)| {
+                            this.child(
+                                div()
+                                    .absolute()
+                                    .left(relative(start))
+                                    .right(relative(1.0 - end))
+                                    .h_full()
+                                    .bg(marker_color)
+                                    .rounded_full(),
+                            )
+                        })
+                        .children(ticks.into_iter().map(|tick| {
                             div()
                                 .absolute()
-                                .left(px(0.))
-                                .right(relative(1.0 - percentage))
-                                .h_full()
-                                .bg(fill_color)
-                                .rounded_full(),
-                        )
+                                .left(relative(tick))
+                                .top_0()
+                                .bottom_0()
+                                .w(px(1.))
+                                .bg(tick_color)
+                        }))
+                        .children(markers.into_iter().map(|marker| {
+                            div()
+                                .absolute()
+                                .left(relative(marker))
+                                .ml(-px(1.))
+                                .top(-px(3.))
+                                .bottom(-px(3.))
+                                .w(px(2.))
+                                .bg(marker_color)
+                        }))
                         .child(
                             div()
                                 .absolute()
@@ -233,8 +368,10 @@ impl RenderOnce for Slider {
                                 .size(px(12.))
                                 .rounded_full(),
                         )
-                        .hover(|this| this.bg(bar_color))
-                        .active(|this| this.bg(bar_color))
+                        .when(self.waveform.is_none(), |this| {
+                            this.hover(|this| this.bg(bar_color))
+                                .active(|this| this.bg(bar_color))
+                        })
                         .on_prepaint({
                             let state = self.state.clone();
                             move |bounds, _, cx| state.update(cx, |s, _| s.bounds = bounds)
@@ -243,3 +380,33 @@ impl RenderOnce for Slider {
         )
     }
 }
+
+fn waveform_columns(waveform: &Waveform) -> Vec<f32> {
+    let peaks = &waveform.peaks;
+
+    if peaks.is_empty() {
+        return Vec::new();
+    }
+
+    let columns: Vec<f32> = (0..WAVEFORM_COLUMNS)
+        .map(|column| {
+            let start = column * peaks.len() / WAVEFORM_COLUMNS;
+            let end = ((column + 1) * peaks.len() / WAVEFORM_COLUMNS)
+                .max(start + 1)
+                .min(peaks.len());
+
+            peaks[start..end]
+                .iter()
+                .map(|(min, max)| (max - min) / 2.0)
+                .fold(0.0, f32::max)
+        })
+        .collect();
+
+    let loudest = columns.iter().copied().fold(0.0, f32::max);
+
+    if loudest > 0.0 {
+        columns.into_iter().map(|c| c / loudest).collect()
+    } else {
+        columns
+    }
+}
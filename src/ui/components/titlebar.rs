@@ -1,3 +1,4 @@
+use super::engine_health::EngineHealthIndicator;
 use super::navbar::NavBar;
 use crate::ui::components::icons::Icons;
 use crate::ui::theme::Theme;
@@ -9,6 +10,7 @@ use gpui::{
 #[derive(Clone)]
 pub struct Titlebar {
     pub navbar: Entity<NavBar>,
+    pub engine_health: Entity<EngineHealthIndicator>,
 }
 
 impl Render for Titlebar {
@@ -84,6 +86,7 @@ impl Render for Titlebar {
                             .h_full()
                             .window_control_area(WindowControlArea::Drag),
                     )
+                    .child(self.engine_health.clone())
                     .child(
                         div()
                             .id("win_close_btn")
@@ -106,7 +109,11 @@ impl Render for Titlebar {
 impl Titlebar {
     pub fn new(cx: &mut App) -> Titlebar {
         let navbar = cx.new(|_| NavBar::new());
+        let engine_health = EngineHealthIndicator::new(cx);
 
-        Titlebar { navbar }
+        Titlebar {
+            navbar,
+            engine_health,
+        }
     }
 }
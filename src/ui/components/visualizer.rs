@@ -0,0 +1,186 @@
+use crate::audio::analysis::Analysis;
+use crate::ui::theme::{DominantColors, Theme};
+use gpui::{
+    App, AppContext, Context, Entity, FontWeight, Global, InteractiveElement, IntoElement,
+    ParentElement, Render, Rgba, StatefulInteractiveElement, Styled, Window, div,
+    gradient_color_stop, linear_gradient, px, relative, rgb,
+};
+
+const DECAY: f32 = 0.85;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct VisualizerStateInner {
+    pub spectrum: Vec<f32>,
+    pub waveform: Vec<f32>,
+    pub rms: f32,
+    pub peak: f32,
+}
+
+pub struct VisualizerState(pub Entity<VisualizerStateInner>);
+
+impl Global for VisualizerState {}
+
+impl VisualizerStateInner {
+    pub fn apply(&mut self, analysis: &Analysis) {
+        if self.spectrum.len() == analysis.spectrum.len() {
+            for (current, value) in self.spectrum.iter_mut().zip(&analysis.spectrum) {
+                *current = value.max(*current * DECAY);
+            }
+        } else {
+            self.spectrum.clone_from(&analysis.spectrum);
+        }
+
+        self.waveform.clone_from(&analysis.waveform);
+        self.rms = analysis.rms;
+        self.peak = analysis.peak.max(self.peak * DECAY);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Bars,
+    Scope,
+}
+
+pub struct Visualizer {
+    mode: Mode,
+}
+
+impl Visualizer {
+    pub fn new(cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| {
+            let state = cx.global::<VisualizerState>().0.clone();
+            cx.observe(&state, |_, _, cx| cx.notify()).detach();
+
+            Self { mode: Mode::Bars }
+        })
+    }
+}
+
+fn is_unset(color: Rgba) -> bool {
+    color.r + color.g + color.b == 0.0
+}
+
+impl Render for Visualizer {
+    #[allow(clippy::too_many_lines)]
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = *cx.global::<Theme>();
+        let dominant_colors = *cx.global::<DominantColors>();
+        let state = cx.global::<VisualizerState>().0.read(cx);
+
+        let (low, high) = if is_unset(dominant_colors.color1) {
+            (theme.playback_slider_fill, rgb(0xFFFFFF))
+        } else {
+            (dominant_colors.color1, dominant_colors.color3)
+        };
+
+        let mode = self.mode;
+
+        let graph =
+            match mode {
+                Mode::Bars => div()
+                    .w_full()
+                    .h_full()
+                    .flex()
+                    .items_end()
+                    .gap_x(px(2.0))
+                    .children(state.spectrum.iter().map(|value| {
+                        div()
+                            .flex_1()
+                            .h(relative(value.max(0.01)))
+                            .rounded_t_sm()
+                            .bg(linear_gradient(
+                                0.0,
+                                gradient_color_stop(low, 0.0),
+                                gradient_color_stop(high, 1.0),
+                            ))
+                    })),
+                Mode::Scope => div().w_full().h_full().flex().items_center().children(
+                    state.waveform.iter().map(|sample| {
+                        div()
+                            .flex_1()
+                            .h(relative(sample.abs().clamp(0.005, 1.0)))
+                            .bg(if *sample >= 0.0 { high } else { low })
+                    }),
+                ),
+            };
+
+        div()
+            .w_full()
+            .h_full()
+            .flex()
+            .flex_col()
+            .gap_y_4()
+            .py_4()
+            .text_sm()
+            .text_color(theme.player_panel_tab_text)
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .font_weight(FontWeight(500.0))
+                            .text_color(theme.player_panel_tab_text_active)
+                            .child("Visualizer"),
+                    )
+                    .child(
+                        div()
+                            .id("visualizer_mode")
+                            .px_2()
+                            .rounded_md()
+                            .cursor_pointer()
+                            .hover(|this| {
+                                this.bg(theme.player_icons_bg_hover)
+                                    .text_color(theme.player_icons_text_hover)
+                            })
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.mode = match this.mode {
+                                    Mode::Bars => Mode::Scope,
+                                    Mode::Scope => Mode::Bars,
+                                };
+                                cx.notify();
+                            }))
+                            .child(match mode {
+                                Mode::Bars => "Bars",
+                                Mode::Scope => "Scope",
+                            }),
+                    ),
+            )
+            .child(div().w_full().flex_1().child(graph))
+            .child(
+                div()
+                    .w_full()
+                    .flex()
+                    .flex_col()
+                    .gap_y_1()
+                    .font_family("JetBrains Mono")
+                    .children(
+                        [("RMS", state.rms), ("Peak", state.peak)].map(|(label, level)| {
+                            div()
+                                .w_full()
+                                .flex()
+                                .items_center()
+                                .gap_x_3()
+                                .child(div().w(px(40.0)).flex_shrink_0().child(label))
+                                .child(
+                                    div()
+                                        .flex_1()
+                                        .h(px(4.0))
+                                        .rounded_full()
+                                        .bg(theme.volume_slider_track)
+                                        .child(
+                                            div()
+                                                .h_full()
+                                                .w(relative(level.clamp(0.0, 1.0)))
+                                                .rounded_full()
+                                                .bg(high),
+                                        ),
+                                )
+                        }),
+                    ),
+            )
+    }
+}
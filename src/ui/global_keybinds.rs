@@ -16,7 +16,8 @@ actions!(
         Shuffle,
         Repeat,
         SeekBack,
-        SeekForward
+        SeekForward,
+        SleepTimer
     ]
 );
 actions!(pages, [CycleNext, CyclePrev, Library, Player, Playlists]);
@@ -30,6 +31,7 @@ pub fn register_keybinds(cx: &mut App) {
     cx.on_action(repeat);
     cx.on_action(seek_forward);
     cx.on_action(seek_back);
+    cx.on_action(sleep_timer);
 
     // Page actions
     cx.on_action(cycle_next);
@@ -57,6 +59,7 @@ pub fn register_keybinds(cx: &mut App) {
 
     cx.bind_keys([KeyBinding::new("shift-s", Shuffle, None)]);
     cx.bind_keys([KeyBinding::new("shift-r", Repeat, None)]);
+    cx.bind_keys([KeyBinding::new("shift-t", SleepTimer, None)]);
 
     // Page binds
     if cfg!(target_os = "macos") {
@@ -117,6 +120,11 @@ fn seek_back(_: &SeekBack, cx: &mut App) {
     controller.seek(current.saturating_sub(Duration::from_secs(5)));
 }
 
+fn sleep_timer(_: &SleepTimer, cx: &mut App) {
+    let controller = cx.global::<Controller>().clone();
+    controller.cycle_sleep_timer(cx);
+}
+
 fn cycle_next(_: &CycleNext, cx: &mut App) {
     let current = *cx.global::<Page>();
 
@@ -1,8 +1,9 @@
 use crate::controller::state::PlaylistId;
 use crate::controller::state::TrackId;
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Timelike};
 use gpui::RenderImage;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 #[must_use]
 #[allow(
@@ -63,3 +64,28 @@ pub fn fingerprint_playlists(ids: impl IntoIterator<Item = PlaylistId>) -> u128
 
     acc
 }
+
+/// The next time the local clock reads `hour:minute`: today if that's still ahead,
+/// tomorrow otherwise.
+#[must_use]
+pub fn next_local_time(hour: u32, minute: u32) -> Option<SystemTime> {
+    let now = Local::now();
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+
+    let date = if now.time() < time {
+        now.date_naive()
+    } else {
+        now.date_naive().succ_opt()?
+    };
+
+    let at = Local.from_local_datetime(&date.and_time(time)).earliest()?;
+
+    Some(at.into())
+}
+
+#[must_use]
+pub fn local_time_of_day(at: SystemTime) -> (u32, u32) {
+    let local = DateTime::<Local>::from(at);
+
+    (local.hour(), local.minute())
+}
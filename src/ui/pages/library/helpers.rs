@@ -1,3 +1,4 @@
+use crate::audio::formats;
 use crate::controller::Controller;
 use crate::controller::state::LibraryState;
 use crate::controller::state::PlaylistId;
@@ -49,30 +50,56 @@ pub(super) fn render_header(kind: &HeaderKind, height: Pixels, cx: &App) -> Div
         .child(heading)
         .child(if *kind == HeaderKind::Playlists {
             div()
-                .id("create_playlist")
+                .id("playlist_header_buttons")
                 .flex()
-                .items_center()
-                .justify_center()
                 .gap_2()
-                .px_4()
-                .py_1()
-                .rounded_lg()
-                .border_1()
-                .border_color(theme.library_header_button_border)
-                .text_color(theme.library_header_button_text)
-                .text_base()
-                .cursor_pointer()
-                .hover(|this| this.bg(theme.library_header_button_bg_hover))
-                .on_click(move |_, _, cx| {
-                    let controller = cx.global::<Controller>().clone();
-                    cx.spawn(async move |_| {
-                        if let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await {
-                            controller.scan_dir(folder.path().into());
-                        }
-                    })
-                    .detach();
-                })
-                .child("Open Folder")
+                .child(
+                    div()
+                        .id("rescan_library")
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .px_4()
+                        .py_1()
+                        .rounded_lg()
+                        .border_1()
+                        .border_color(theme.library_header_button_border)
+                        .text_color(theme.library_header_button_text)
+                        .text_base()
+                        .cursor_pointer()
+                        .hover(|this| this.bg(theme.library_header_button_bg_hover))
+                        .on_click(|_, _, cx| cx.global::<Controller>().rescan_library(cx))
+                        .child("Rescan"),
+                )
+                .child(
+                    div()
+                        .id("create_playlist")
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .gap_2()
+                        .px_4()
+                        .py_1()
+                        .rounded_lg()
+                        .border_1()
+                        .border_color(theme.library_header_button_border)
+                        .text_color(theme.library_header_button_text)
+                        .text_base()
+                        .cursor_pointer()
+                        .hover(|this| this.bg(theme.library_header_button_bg_hover))
+                        .on_click(move |_, _, cx| {
+                            let controller = cx.global::<Controller>().clone();
+                            cx.spawn(async move |_| {
+                                if let Some(folder) =
+                                    rfd::AsyncFileDialog::new().pick_folder().await
+                                {
+                                    controller.scan_dir(folder.path().into());
+                                }
+                            })
+                            .detach();
+                        })
+                        .child("Open Folder"),
+                )
         } else if *kind == HeaderKind::Tracks {
             div()
                 .id("add_track")
@@ -92,7 +119,12 @@ pub(super) fn render_header(kind: &HeaderKind, height: Pixels, cx: &App) -> Div
                 .on_click(move |_, _, cx| {
                     let controller = cx.global::<Controller>().clone();
                     cx.spawn(async move |_| {
-                        if let Some(files) = rfd::AsyncFileDialog::new().pick_files().await {
+                        if let Some(files) = rfd::AsyncFileDialog::new()
+                            .add_filter("Audio", &formats::extensions())
+                            .add_filter("Radio stations", &["m3u", "pls"])
+                            .pick_files()
+                            .await
+                        {
                             for file in files {
                                 controller.scan_track(file.path().into());
                             }
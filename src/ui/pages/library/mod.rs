@@ -11,7 +11,7 @@ use gpui::prelude::FluentBuilder;
 use gpui::{
     App, Context, Div, FontWeight, ImageSource, InteractiveElement, IntoElement, ObjectFit,
     ParentElement, Pixels, Render, ScrollHandle, StatefulInteractiveElement, Styled, StyledImage,
-    VirtualListScrollController, Window, div, img, vlist,
+    VirtualListScrollController, Window, div, img, relative, vlist,
 };
 use helpers::{LibraryRow, build_rows, render_header, render_playlist_grid, render_track_table_header, HeaderKind};
 use std::rc::Rc;
@@ -63,6 +63,13 @@ impl LibraryPage {
         let is_current = Some(id) == state.playback.current.as_ref();
 
         if let Some(track) = state.library.tracks.get(id) {
+            let progress = state
+                .resume
+                .positions
+                .get(id)
+                .filter(|_| !track.duration.is_zero())
+                .map(|position| (position.as_secs_f32() / track.duration.as_secs_f32()).min(1.0));
+
             div()
                 .h(height)
                 .py_1()
@@ -78,6 +85,7 @@ impl LibraryPage {
                         .cursor_pointer()
                         .hover(|this| this.bg(theme.library_track_bg_hover))
                         .when(is_current, |this| this.bg(theme.library_track_bg_active))
+                        .when(track.is_unplayable(), |this| this.opacity(0.4))
                         .on_click({
                             let id = *id;
                             move |_, _, cx| {
@@ -171,8 +179,8 @@ impl LibraryPage {
                                 .h_full()
                                 .px_4()
                                 .flex()
-                                .items_center()
-                                .justify_start()
+                                .flex_col()
+                                .justify_center()
                                 .text_sm()
                                 .font_family("JetBrains Mono")
                                 .child(format!(
@@ -180,6 +188,23 @@ impl LibraryPage {
                                     track.duration.as_secs() / 60,
                                     track.duration.as_secs() % 60
                                 ))
+                                .when_some(progress, |this, progress| {
+                                    this.child(
+                                        div()
+                                            .mt_1()
+                                            .w_full()
+                                            .h_0p5()
+                                            .rounded_full()
+                                            .bg(theme.library_track_progress_track)
+                                            .child(
+                                                div()
+                                                    .h_full()
+                                                    .w(relative(progress))
+                                                    .rounded_full()
+                                                    .bg(theme.library_track_progress_fill),
+                                            ),
+                                    )
+                                })
                                 .overflow_hidden()
                                 .whitespace_nowrap()
                                 .text_ellipsis(),
@@ -3,12 +3,15 @@ use crate::{
     ui::{
         components::{
             bounds_observer::observe_bounds,
+            chapters::ChapterList,
             controlbar::ControlBar,
+            equalizer::EqualizerPanel,
             icons::{Icon, Icons},
             image_cache::ImageCache,
             lyrics::LyricsView,
             queue::Queue,
             scrollbar::{RightPad, floating_scrollbar},
+            visualizer::Visualizer,
         },
         theme::{DominantColors, Theme},
     },
@@ -27,6 +30,9 @@ pub struct PlayerPage {
     pub lyrics: Entity<LyricsView>,
     lyrics_scroll_handle: UniformListScrollHandle,
     pub controlbar: Entity<ControlBar>,
+    pub equalizer: Entity<EqualizerPanel>,
+    pub visualizer: Entity<Visualizer>,
+    pub chapters: Entity<ChapterList>,
     show_panel: Entity<bool>,
     current_panel: Entity<Panel>,
     album_bounds: Option<Bounds<Pixels>>,
@@ -36,6 +42,9 @@ pub struct PlayerPage {
 enum Panel {
     Lyrics,
     Queue,
+    Equalizer,
+    Visualizer,
+    Chapters,
 }
 
 impl PlayerPage {
@@ -50,6 +59,9 @@ impl PlayerPage {
             lyrics: LyricsView::new(cx, ScrollHandle::new()),
             lyrics_scroll_handle: UniformListScrollHandle::new(),
             controlbar,
+            equalizer: EqualizerPanel::new(cx),
+            visualizer: Visualizer::new(cx),
+            chapters: ChapterList::new(cx),
             show_panel,
             current_panel,
             album_bounds: None,
@@ -75,6 +87,8 @@ impl Render for PlayerPage {
         } else {
             None
         };
+        // Radio shows what the station says is playing, with the station underneath.
+        let stream_title = state.playback.current_stream_title();
 
         let gradient_pos = self.album_bounds.map(|bounds| {
             let center_x = bounds.origin.x + bounds.size.width / 2.0;
@@ -189,7 +203,9 @@ impl Render for PlayerPage {
                                             .font_weight(FontWeight(500.0))
                                             .max_w_96()
                                             .truncate()
-                                            .child(track.title.clone()),
+                                            .child(
+                                                stream_title.unwrap_or(&track.title).to_string(),
+                                            ),
                                     )
                                     .child(
                                         div()
@@ -198,7 +214,11 @@ impl Render for PlayerPage {
                                             .font_weight(FontWeight(400.0))
                                             .max_w_96()
                                             .truncate()
-                                            .child(track.artist.clone()),
+                                            .child(if stream_title.is_some() {
+                                                track.title.clone()
+                                            } else {
+                                                track.artist.clone()
+                                            }),
                                     ),
                             )
                     } else {
@@ -347,7 +367,7 @@ impl Render for PlayerPage {
                 div()
                     .h_full()
                     .w(relative(0.46))
-                    .when(*self.current_panel.read(cx) == Panel::Queue, |this| {
+                    .when(*self.current_panel.read(cx) != Panel::Lyrics, |this| {
                         this.max_w_128()
                     })
                     .flex_shrink_0()
@@ -357,10 +377,12 @@ impl Render for PlayerPage {
                     .child({
                         let current_panel = self.current_panel.clone();
 
-                        let active_left = if *current_panel.read(cx) == Panel::Queue {
-                            px(0.0)
-                        } else {
-                            px(72.0)
+                        let active_left = match *current_panel.read(cx) {
+                            Panel::Queue => px(0.0),
+                            Panel::Lyrics => px(72.0),
+                            Panel::Equalizer => px(144.0),
+                            Panel::Visualizer => px(216.0),
+                            Panel::Chapters => px(288.0),
                         };
 
                         div()
@@ -403,6 +425,7 @@ impl Render for PlayerPage {
                                                     current_panel.update(cx, |p, _| {
                                                         *p = Panel::Queue;
                                                     });
+                                                    cx.global::<Controller>().set_analysis(false);
                                                 }
                                             })
                                             .child(
@@ -434,6 +457,7 @@ impl Render for PlayerPage {
                                                     current_panel.update(cx, |p, _| {
                                                         *p = Panel::Lyrics;
                                                     });
+                                                    cx.global::<Controller>().set_analysis(false);
                                                 }
                                             })
                                             .child(
@@ -450,6 +474,108 @@ impl Render for PlayerPage {
                                                     )
                                                     .child("Lyrics"),
                                             )
+                                    })
+                                    .child({
+                                        let current_panel = current_panel.clone();
+
+                                        div()
+                                            .id("panel_switcher_equalizer")
+                                            .w(px(48.0))
+                                            .flex()
+                                            .justify_center()
+                                            .cursor_pointer()
+                                            .on_click({
+                                                let current_panel = current_panel.clone();
+                                                move |_, _, cx| {
+                                                    current_panel.update(cx, |p, _| {
+                                                        *p = Panel::Equalizer;
+                                                    });
+                                                    cx.global::<Controller>().set_analysis(false);
+                                                }
+                                            })
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .font_weight(FontWeight(500.0))
+                                                    .text_color(
+                                                        if *current_panel.read(cx)
+                                                            == Panel::Equalizer
+                                                        {
+                                                            theme.player_panel_tab_text_active
+                                                        } else {
+                                                            theme.player_panel_tab_text
+                                                        },
+                                                    )
+                                                    .child("EQ"),
+                                            )
+                                    })
+                                    .child({
+                                        let current_panel = current_panel.clone();
+
+                                        div()
+                                            .id("panel_switcher_visualizer")
+                                            .w(px(48.0))
+                                            .flex()
+                                            .justify_center()
+                                            .cursor_pointer()
+                                            .on_click({
+                                                let current_panel = current_panel.clone();
+                                                move |_, _, cx| {
+                                                    current_panel.update(cx, |p, _| {
+                                                        *p = Panel::Visualizer;
+                                                    });
+                                                    cx.global::<Controller>().set_analysis(true);
+                                                }
+                                            })
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .font_weight(FontWeight(500.0))
+                                                    .text_color(
+                                                        if *current_panel.read(cx)
+                                                            == Panel::Visualizer
+                                                        {
+                                                            theme.player_panel_tab_text_active
+                                                        } else {
+                                                            theme.player_panel_tab_text
+                                                        },
+                                                    )
+                                                    .child("Visual"),
+                                            )
+                                    })
+                                    .child({
+                                        let current_panel = current_panel.clone();
+
+                                        div()
+                                            .id("panel_switcher_chapters")
+                                            .w(px(48.0))
+                                            .flex()
+                                            .justify_center()
+                                            .cursor_pointer()
+                                            .on_click({
+                                                let current_panel = current_panel.clone();
+                                                move |_, _, cx| {
+                                                    current_panel.update(cx, |p, _| {
+                                                        *p = Panel::Chapters;
+                                                    });
+                                                    cx.global::<Controller>().set_analysis(false);
+                                                }
+                                            })
+                                            .child(
+                                                div()
+                                                    .text_sm()
+                                                    .font_weight(FontWeight(500.0))
+                                                    .text_color(
+                                                        if *current_panel.read(cx)
+                                                            == Panel::Chapters
+                                                        {
+                                                            theme.player_panel_tab_text_active
+                                                        } else {
+                                                            theme.player_panel_tab_text
+                                                        },
+                                                    )
+                                                    .child("Chapters"),
+                                            )
                                     }),
                             )
                     })
@@ -457,7 +583,26 @@ impl Render for PlayerPage {
                         let current_panel = self.current_panel.clone();
 
                         div().w_full().h_full().px_4().flex().relative().child({
-                            if *current_panel.read(cx) == Panel::Queue {
+                            if *current_panel.read(cx) == Panel::Visualizer {
+                                div()
+                                    .id("visualizer_container")
+                                    .w_full()
+                                    .h_full()
+                                    .child(self.visualizer.clone())
+                            } else if *current_panel.read(cx) == Panel::Chapters {
+                                div()
+                                    .id("chapters_container")
+                                    .w_full()
+                                    .h_full()
+                                    .child(self.chapters.clone())
+                            } else if *current_panel.read(cx) == Panel::Equalizer {
+                                div()
+                                    .id("equalizer_container")
+                                    .w_full()
+                                    .h_full()
+                                    .overflow_y_scroll()
+                                    .child(self.equalizer.clone())
+                            } else if *current_panel.read(cx) == Panel::Queue {
                                 div()
                                     .id("queue_container")
                                     .w_full()
@@ -1,15 +1,20 @@
 use crate::controller::Controller;
+use crate::controller::state::Alarm;
 use crate::controller::state::LibraryState;
 use crate::controller::state::PlaylistId;
 use crate::controller::state::TrackId;
 use crate::ui::components::Page;
 use crate::ui::components::icons::{Icon, Icons};
 use crate::ui::components::image_cache::ImageCache;
+use crate::ui::helpers::{local_time_of_day, next_local_time};
 use crate::ui::theme::Theme;
 use gpui::{
     App, Div, FontWeight, ImageSource, InteractiveElement, ObjectFit, ParentElement, Pixels,
     StatefulInteractiveElement, Styled, StyledImage, div, img, px, rems,
 };
+use std::time::SystemTime;
+
+const ALARM_TIMES: [(u32, u32); 7] = [(6, 0), (6, 30), (7, 0), (7, 30), (8, 0), (8, 30), (9, 0)];
 
 pub(super) enum PlaylistsRows {
     Header,
@@ -28,6 +33,12 @@ pub(super) fn render_header(height: Pixels, id: Option<PlaylistId>, cx: &mut App
     {
         controller.request_playlist_thumbnails(&[id], cx);
 
+        let alarm = state
+            .alarm
+            .alarm
+            .filter(|alarm| alarm.playlist == id)
+            .map(|alarm| alarm.at);
+
         let cache = cx.global_mut::<ImageCache>();
         let thumbnail = playlist.image_id.and_then(|id| cache.get(&id));
 
@@ -131,6 +142,40 @@ pub(super) fn render_header(height: Pixels, id: Option<PlaylistId>, cx: &mut App
                                             *cx.global_mut::<Page>() = Page::Player;
                                         }
                                     }),
+                            )
+                            .child(
+                                div()
+                                    .id("playlist_alarm")
+                                    .py_1()
+                                    .px_4()
+                                    .text_base()
+                                    .text_color(theme.playlist_header_button_text)
+                                    .bg(theme.playlist_header_button_bg)
+                                    .border_2()
+                                    .border_color(theme.playlist_header_button_border)
+                                    .rounded_md()
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .child(match alarm {
+                                        Some(at) => {
+                                            let (hour, minute) = local_time_of_day(at);
+                                            format!("Alarm {hour:02}:{minute:02}")
+                                        }
+                                        None => "Set Alarm".to_string(),
+                                    })
+                                    .cursor_pointer()
+                                    .hover(|this| this.bg(theme.playlist_header_button_hover))
+                                    .on_click({
+                                        let id = playlist.id;
+                                        move |_, _, cx| {
+                                            let controller = cx.global::<Controller>().clone();
+                                            let alarm = next_alarm_time(alarm)
+                                                .map(|at| Alarm { playlist: id, at });
+
+                                            controller.set_alarm(alarm, cx);
+                                        }
+                                    }),
                             ),
                     ),
             )
@@ -139,6 +184,16 @@ pub(super) fn render_header(height: Pixels, id: Option<PlaylistId>, cx: &mut App
     }
 }
 
+// Steps through the preset wake-up times, then back to no alarm.
+fn next_alarm_time(current: Option<SystemTime>) -> Option<SystemTime> {
+    let (hour, minute) = match current.map(local_time_of_day) {
+        None => ALARM_TIMES[0],
+        Some(current) => ALARM_TIMES.iter().copied().find(|&step| step > current)?,
+    };
+
+    next_local_time(hour, minute)
+}
+
 pub(super) fn render_track_table_header(height: Pixels, cx: &mut App) -> Div {
     let theme = cx.global::<Theme>();
 
@@ -1,5 +1,6 @@
 use crate::controller::events::{
-    AudioEvent, CacherEvent, ImageProcessorEvent, LyricsEvent, ScannerEvent, SystemIntegrationEvent,
+    AudioEvent, CacherEvent, ImageProcessorEvent, LyricsEvent, ScannerEvent, SupervisorEvent,
+    SystemIntegrationEvent,
 };
 use gpui::{Context, EventEmitter};
 
@@ -10,6 +11,7 @@ pub enum Event {
     ImageProcessor(ImageProcessorEvent),
     SystemIntegration(SystemIntegrationEvent),
     LyricsEvent(LyricsEvent),
+    Supervisor(SupervisorEvent),
 }
 
 #[derive(Clone, Copy)]
@@ -10,6 +10,7 @@ pub struct Theme {
     pub titlebar_bg: Rgba,
     pub titlebar_window_icons_text: Rgba,
     pub titlebar_window_icons_bg_hover: Rgba,
+    pub titlebar_engine_restarting: Rgba,
 
     // Page Switcher
     pub switcher_bg: Rgba,
@@ -66,6 +67,8 @@ pub struct Theme {
     // Controlbar
     pub playback_slider_track: Rgba,
     pub playback_slider_fill: Rgba,
+    pub playback_slider_loop: Rgba,
+    pub playback_slider_chapter: Rgba,
     pub playback_position_text: Rgba,
     pub volume_icon: Rgba,
     pub volume_slider_track: Rgba,
@@ -94,6 +97,8 @@ pub struct Theme {
     pub library_track_bg_hover: Rgba,
     pub library_track_bg_active: Rgba,
     pub library_track_title_text_active: Rgba,
+    pub library_track_progress_track: Rgba,
+    pub library_track_progress_fill: Rgba,
 
     pub library_empty_text: Rgba,
 
@@ -151,6 +156,7 @@ impl Default for Theme {
             titlebar_bg: rgb(0x050505),
             titlebar_window_icons_text: rgba(0xFFFFFFCC),
             titlebar_window_icons_bg_hover: rgba(0xFFFFFF14),
+            titlebar_engine_restarting: rgb(0xF59E0B),
 
             switcher_bg: rgba(0xFFFFFF0A),
             switcher_active: rgb(0xF5F5F5),
@@ -207,6 +213,8 @@ impl Default for Theme {
 
             playback_slider_track: rgba(0xFFFFFF14),
             playback_slider_fill: rgb(0xFAFAFA),
+            playback_slider_loop: rgba(0x60A5FA80),
+            playback_slider_chapter: rgba(0x09090BCC),
             playback_position_text: rgb(0x71717A),
 
             volume_icon: rgb(0x71717A),
@@ -237,6 +245,8 @@ impl Default for Theme {
             library_track_bg_hover: rgba(0xFFFFFF08),
             library_track_bg_active: rgba(0xFFFFFF10),
             library_track_title_text_active: rgb(0xFFFFFF),
+            library_track_progress_track: rgba(0xFFFFFF14),
+            library_track_progress_fill: rgb(0xA1A1AA),
 
             library_empty_text: rgb(0x71717A),
 
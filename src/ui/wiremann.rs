@@ -1,10 +1,12 @@
 use crate::controller::Controller;
 use crate::ui::animations::ease_in_out_expo;
 use crate::ui::components::controlbar::ControlBar;
+use crate::ui::components::engine_health::{EngineHealthInner, EngineHealthState};
 use crate::ui::components::lyrics::{LyricsState, LyricsStateInner};
 use crate::ui::components::slider::{SliderEvent, SliderState};
 use crate::ui::components::toasts::ToastManager;
 use crate::ui::components::toasts::scanning_status::ScanningStatus;
+use crate::ui::components::visualizer::{VisualizerState, VisualizerStateInner};
 use crate::ui::helpers::slider_to_duration;
 use crate::ui::pages::{library::LibraryPage, player::PlayerPage, playlists::PlaylistsPage};
 use crate::ui::theme::{DominantColors, Theme};
@@ -90,6 +92,10 @@ impl Wiremann {
         cx.set_global(scanning_status);
         let lyrics_state = LyricsState(cx.new(|_| LyricsStateInner::new()));
         cx.set_global(lyrics_state);
+        let visualizer_state = VisualizerState(cx.new(|_| VisualizerStateInner::default()));
+        cx.set_global(visualizer_state);
+        let engine_health = EngineHealthState(cx.new(|_| EngineHealthInner::default()));
+        cx.set_global(engine_health);
 
         global_keybinds::register_keybinds(cx);
 